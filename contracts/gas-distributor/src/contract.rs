@@ -1,71 +1,495 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_std::{
-    attr, ensure, entry_point, to_json_binary, Addr, Attribute, BankMsg, Binary, Coin, CosmosMsg,
-    Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
+    attr, ensure, entry_point, from_json, to_json_binary, to_json_string, Addr, Attribute, BankMsg,
+    Binary, BlockInfo, Coin, CosmosMsg, Decimal, Deps, DepsMut, Empty, Env, Event, MessageInfo,
+    Order, QueryRequest, Reply, Response, StdError, StdResult, Storage, SubMsg, SubMsgResult,
+    Timestamp, Uint128, WasmMsg,
 };
+use cw20::{AllowanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_storage_plus::Bound;
+use cw_utils::{Duration, Expiration};
 use drop_helper_contracts_base::{
     error::gas_distributor::ContractError,
-    msg::gas_distributor::{ExecuteMsg, InstantiateMsg, QueryMsg, TargetBalance},
-    state::gas_distributor::{TARGET_BALANCES, UNTRN_DENOM},
+    msg::gas_distributor::{
+        router::{
+            AssetInfo, ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg,
+            SimulateSwapOperationsResponse, SwapOperation,
+        },
+        treasury::ExecuteMsg as TreasuryExecuteMsg,
+        BalanceQuery, Config, ConfigOptional, CronSchedule, Deposit, ExecuteMsg, FundingSource,
+        FundingSourceKind, InstantiateMsg, MigrateMsg, PauseInfo, PendingOperation,
+        PlannedTransfer, QueryMsg, RefillConfig, RemoteTarget, Role, ShortfallPolicy,
+        SimulateDistributeResponse, SkipReason, SkippedTarget, SpendingAllowance, SpendingCap,
+        SponsorAccount, StaleTarget, SudoMsg, TargetBalance, TargetBalanceUpdateParams,
+        TargetStatus, TimelockedOperation, Token, TopUp, WithdrawAllowance, WithdrawLimit,
+    },
+    state::gas_distributor::{
+        CONFIG, CRON_SCHEDULE, DEPOSITS, FUNDING_SOURCE, LAST_REFILL, LAST_TOP_UPS,
+        NEXT_OPERATION_ID, PAUSE, PENDING_BALANCE_QUERIES, PENDING_OPERATIONS, PENDING_PULL,
+        PULL_HISTORY, REFILL_CONFIG, REMOTE_BALANCE_QUERIES, REMOTE_TARGETS, ROLES,
+        SPONSORED_TOTALS, SPONSOR_BALANCES, SPONSOR_SPENT, TARGET_BALANCES, TOP_UP_HISTORY,
+        TREASURER_LIMITS, TREASURER_WITHDRAWALS, UNTRN_DENOM, WITHDRAW_HISTORY,
+    },
+};
+use drop_helper_contracts_helpers::answer::{event, response};
+use neutron_sdk::{
+    bindings::{
+        msg::{IbcFee, MsgExecuteContract, MsgRegisterInterchainQueryResponse, NeutronMsg},
+        query::NeutronQuery,
+    },
+    interchain_queries::{
+        v045::register_queries::new_register_balances_query_msg, v047::queries::query_balance,
+    },
+    query::min_ibc_fee::MinIbcFeeResponse,
+    sudo::msg::{RequestPacket, RequestPacketTimeoutHeight},
 };
-use drop_helper_contracts_helpers::answer::response;
-use neutron_sdk::bindings::msg::NeutronMsg;
+
+use crate::migrations::migrate_storage;
 
 const CONTRACT_NAME: &str = concat!("crates.io:drop-helper__", env!("CARGO_PKG_NAME"));
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+const BALANCE_QUERY_REPLY_ID: u64 = 1;
+const PULL_REPLY_ID: u64 = 2;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    deps: DepsMut,
+    deps: DepsMut<NeutronQuery>,
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> Result<Response, ContractError> {
-    let mut attrs = vec![];
+) -> Result<Response<NeutronMsg>, ContractError> {
     cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     let owner = deps
         .api
         .addr_validate(msg.owner.unwrap_or(info.sender).as_str())?;
     cw_ownable::initialize_owner(deps.storage, deps.api, Some(owner.as_str()))?;
-    for target_balance in msg.initial_target_balances.clone() {
-        deps.api.addr_validate(target_balance.address.as_str())?;
-        attrs.push(attr(
-            "add-target-balance",
-            target_balance.address.to_string(),
-        ));
-    }
-    msg.initial_target_balances
+    let mut config = Config::default();
+    update_config(deps.as_ref(), &mut config, msg.config.unwrap_or_default())?;
+    CONFIG.save(deps.storage, &config)?;
+    let attrs = msg
+        .initial_target_balances
         .iter()
-        .for_each(|target_balance| target_balance.validate(deps.as_ref()));
-    TARGET_BALANCES.save(deps.storage, &msg.initial_target_balances)?;
-    Ok(response("instantiate", CONTRACT_NAME, attrs))
+        .map(|target_balance| attr("add-target-balance", target_balance.address.clone()))
+        .collect::<Vec<_>>();
+    let messages = save_target_balances(deps, &msg.initial_target_balances)?;
+    Ok(response("instantiate", CONTRACT_NAME, attrs).add_submessages(messages))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
+pub fn query(deps: Deps<NeutronQuery>, env: Env, msg: QueryMsg) -> Result<Binary, ContractError> {
     Ok(match msg {
-        QueryMsg::TargetBalances {} => query_target_balances(deps)?,
-        QueryMsg::TargetBalance { address } => query_target_balance(deps, address)?,
+        QueryMsg::TargetBalances { start_after, limit } => {
+            query_target_balances(deps, start_after, limit)?
+        }
+        QueryMsg::TargetBalance { address, denom } => query_target_balance(deps, address, denom)?,
+        QueryMsg::TargetStatuses { start_after, limit } => {
+            query_target_statuses(deps, env, start_after, limit)?
+        }
+        QueryMsg::TargetStatus { address, denom } => {
+            let update_options = TARGET_BALANCES
+                .may_load(deps.storage, (&address, denom.as_str()))?
+                .ok_or(ContractError::UnknownTargetBalance)?;
+            let config = CONFIG.load(deps.storage)?;
+            to_json_binary(&target_status(
+                deps,
+                &env,
+                &config,
+                address,
+                denom,
+                update_options,
+            )?)?
+        }
+        QueryMsg::Config {} => to_json_binary(&CONFIG.load(deps.storage)?)?,
+        QueryMsg::SimulateDistribute {} => query_simulate_distribute(deps, env)?,
+        QueryMsg::LastTopUp { address, denom } => {
+            to_json_binary(&LAST_TOP_UPS.may_load(deps.storage, (&address, denom.as_str()))?)?
+        }
+        QueryMsg::CronSchedule {} => to_json_binary(&CRON_SCHEDULE.may_load(deps.storage)?)?,
+        QueryMsg::RefillConfig {} => to_json_binary(&REFILL_CONFIG.may_load(deps.storage)?)?,
+        QueryMsg::FundingSource {} => to_json_binary(&FUNDING_SOURCE.may_load(deps.storage)?)?,
+        QueryMsg::BalanceQueries { start_after, limit } => {
+            query_balance_queries(deps, start_after, limit)?
+        }
+        QueryMsg::StaleTargets { start_after, limit } => {
+            query_stale_targets(deps, env, start_after, limit)?
+        }
+        QueryMsg::Deposits { start_after, limit } => query_deposits(deps, start_after, limit)?,
+        QueryMsg::SponsorAccounts { start_after, limit } => {
+            query_sponsor_accounts(deps, start_after, limit)?
+        }
+        QueryMsg::SpendingAllowances { start_after, limit } => {
+            query_spending_allowances(deps, env, start_after, limit)?
+        }
+        QueryMsg::RoleHolders {
+            role,
+            start_after,
+            limit,
+        } => query_role_holders(deps, role, start_after, limit)?,
+        QueryMsg::WithdrawAllowances { address } => query_withdraw_allowances(deps, env, address)?,
+        QueryMsg::PauseStatus {} => to_json_binary(&PAUSE.may_load(deps.storage)?)?,
+        QueryMsg::PendingOperations { start_after, limit } => {
+            query_pending_operations(deps, start_after, limit)?
+        }
         QueryMsg::Ownership {} => to_json_binary(&cw_ownable::get_ownership(deps.storage)?.owner)?,
     })
 }
 
-fn query_target_balance(deps: Deps, address: Addr) -> Result<Binary, ContractError> {
-    Ok(to_json_binary(
-        &TARGET_BALANCES
-            .load(deps.storage)?
-            .into_iter()
-            .find(|target_balance| target_balance.address == address)
-            .ok_or(ContractError::UnknownTargetBalance)?,
-    )?)
+fn query_target_balance(
+    deps: Deps<NeutronQuery>,
+    address: Addr,
+    denom: String,
+) -> Result<Binary, ContractError> {
+    let update_options = TARGET_BALANCES
+        .may_load(deps.storage, (&address, denom.as_str()))?
+        .ok_or(ContractError::UnknownTargetBalance)?;
+    let remote = REMOTE_TARGETS.may_load(deps.storage, (&address, denom.as_str()))?;
+    Ok(to_json_binary(&TargetBalance {
+        address: address.to_string(),
+        token: Token::from_key(&denom),
+        update_options,
+        remote,
+    })?)
+}
+
+fn query_target_balances(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(address, denom)| Bound::exclusive((address, denom.as_str())));
+    let target_balances = TARGET_BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((address, denom), update_options) = item?;
+            let remote = REMOTE_TARGETS.may_load(deps.storage, (&address, denom.as_str()))?;
+            Ok(TargetBalance {
+                address: address.to_string(),
+                token: Token::from_key(&denom),
+                update_options,
+                remote,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&target_balances)?)
+}
+
+fn query_target_statuses(
+    deps: Deps<NeutronQuery>,
+    env: Env,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(address, denom)| Bound::exclusive((address, denom.as_str())));
+    let statuses = TARGET_BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((address, denom), update_options) = item?;
+            target_status(deps, &env, &config, address, denom, update_options)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&statuses)?)
+}
+
+/// Only the target itself is planned, so the cost doesn't grow with the amount of targets.
+/// Targets sharing the balance and IBC fees aren't accounted for, unlike in `SimulateDistribute`
+fn target_status(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+    address: Addr,
+    denom: String,
+    update_options: TargetBalanceUpdateParams,
+) -> StdResult<TargetStatus> {
+    let last_top_up = LAST_TOP_UPS.may_load(deps.storage, (&address, denom.as_str()))?;
+    let mut planned = plan_target(deps, env, config, address, denom, update_options.clone())?;
+    let contract_balance =
+        query_token_balance(deps, &env.contract.address, &planned.denom).unwrap_or_default();
+    let available = match &planned.sponsor {
+        Some(sponsor) => SPONSOR_BALANCES
+            .may_load(deps.storage, (sponsor, planned.denom.as_str()))?
+            .unwrap_or_default()
+            .min(contract_balance),
+        None => contract_balance.saturating_sub(sponsored_balance(deps.storage, &planned.denom)?),
+    };
+    planned.amount = allocate_funds(
+        &config.shortfall_policy,
+        available,
+        std::slice::from_ref(&planned),
+    )
+    .into_iter()
+    .next()
+    .unwrap_or_default();
+    let skip_reason = match planned.outcome() {
+        DistributionOutcome::Skipped(reason) => Some(reason),
+        DistributionOutcome::Funded | DistributionOutcome::PartiallyFunded => None,
+    };
+    Ok(TargetStatus {
+        below_threshold: planned.current_balance < update_options.threshold_balance,
+        target: TargetBalance {
+            address: planned.address.to_string(),
+            token: Token::from_key(&planned.denom),
+            update_options,
+            remote: planned.remote,
+        },
+        balance: planned.current_balance,
+        deficit: planned.deficit,
+        max_amount: planned.amount,
+        skip_reason,
+        last_top_up,
+    })
+}
+
+fn query_balance_queries(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(address, denom)| Bound::exclusive((address, denom.as_str())));
+    let balance_queries = REMOTE_BALANCE_QUERIES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((address, denom), query_id) = item?;
+            Ok(BalanceQuery {
+                address: address.to_string(),
+                denom,
+                query_id,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&balance_queries)?)
+}
+
+fn query_stale_targets(
+    deps: Deps<NeutronQuery>,
+    env: Env,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(address, denom)| Bound::exclusive((address, denom.as_str())));
+    let mut stale_targets = vec![];
+    for item in REMOTE_BALANCE_QUERIES.keys(deps.storage, start, None, Order::Ascending) {
+        if stale_targets.len() == limit {
+            break;
+        }
+        let (address, denom) = item?;
+        let key = (&address, denom.as_str());
+        let remote = REMOTE_TARGETS.load(deps.storage, key)?;
+        // targets without results are reported as unavailable, not stale
+        if let Some((_, last_submitted_height)) = query_remote_balance(deps, &env, key, &remote)? {
+            let last_top_up = LAST_TOP_UPS.may_load(deps.storage, key)?;
+            if is_stale_balance(
+                &config,
+                &env.block,
+                last_submitted_height,
+                last_top_up.as_ref(),
+            ) {
+                stale_targets.push(StaleTarget {
+                    address: address.to_string(),
+                    denom,
+                    last_submitted_height,
+                });
+            }
+        }
+    }
+    Ok(to_json_binary(&stale_targets)?)
+}
+
+fn query_deposits(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(depositor, denom)| Bound::exclusive((depositor, denom.as_str())));
+    let deposits = DEPOSITS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((depositor, denom), amount) = item?;
+            Ok(Deposit {
+                depositor: depositor.to_string(),
+                denom,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&deposits)?)
+}
+
+fn query_sponsor_accounts(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(sponsor, denom)| Bound::exclusive((sponsor, denom.as_str())));
+    let accounts = SPONSOR_BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((sponsor, denom), balance) = item?;
+            let spent = SPONSOR_SPENT
+                .may_load(deps.storage, (&sponsor, denom.as_str()))?
+                .unwrap_or_default();
+            Ok(SponsorAccount {
+                sponsor: sponsor.to_string(),
+                denom,
+                balance,
+                spent,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&accounts)?)
+}
+
+fn query_spending_allowances(
+    deps: Deps<NeutronQuery>,
+    env: Env,
+    start_after: Option<(Addr, String)>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(address, denom)| Bound::exclusive((address, denom.as_str())));
+    let allowances = TARGET_BALANCES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((address, denom), update_options) = item?;
+            let (spent, remaining) = match update_options.spending_cap {
+                Some(spending_cap) => {
+                    let spent =
+                        spent_within_period(deps, &env.block, &address, &denom, &spending_cap)?;
+                    (spent, Some(spending_cap.amount.saturating_sub(spent)))
+                }
+                None => (Uint128::zero(), None),
+            };
+            Ok(SpendingAllowance {
+                address: address.to_string(),
+                denom,
+                spent,
+                remaining,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&allowances)?)
+}
+
+fn query_role_holders(
+    deps: Deps<NeutronQuery>,
+    role: Role,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_ref().map(Bound::exclusive);
+    let holders = ROLES
+        .prefix(role.key())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|address| address.to_string()))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&holders)?)
+}
+
+fn query_withdraw_allowances(
+    deps: Deps<NeutronQuery>,
+    env: Env,
+    address: Addr,
+) -> Result<Binary, ContractError> {
+    let allowances = TREASURER_LIMITS
+        .prefix(&address)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, cap) = item?;
+            let withdrawn =
+                withdrawn_within_period(deps.storage, &env.block, &address, &denom, &cap)?;
+            Ok(WithdrawAllowance {
+                denom,
+                remaining: cap.amount.saturating_sub(withdrawn),
+                cap,
+                withdrawn,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&allowances)?)
+}
+
+fn query_pending_operations(
+    deps: Deps<NeutronQuery>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Binary, ContractError> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let operations = PENDING_OPERATIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, operation)| operation))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(to_json_binary(&operations)?)
 }
 
-fn query_target_balances(deps: Deps) -> Result<Binary, ContractError> {
-    Ok(to_json_binary(&TARGET_BALANCES.load(deps.storage)?)?)
+fn query_simulate_distribute(deps: Deps<NeutronQuery>, env: Env) -> Result<Binary, ContractError> {
+    let distribution = plan_distribution(deps, &env)?;
+    let mut transfers = vec![];
+    let mut skipped = vec![];
+    for target in distribution.targets {
+        match target.outcome() {
+            DistributionOutcome::Skipped(reason) => skipped.push(SkippedTarget {
+                address: target.address.to_string(),
+                denom: target.denom,
+                reason,
+            }),
+            DistributionOutcome::Funded | DistributionOutcome::PartiallyFunded => {
+                transfers.push(PlannedTransfer {
+                    address: target.address.to_string(),
+                    amount: Coin {
+                        denom: target.denom,
+                        amount: target.amount,
+                    },
+                    deficit: target.deficit,
+                })
+            }
+        }
+    }
+    Ok(to_json_binary(&SimulateDistributeResponse {
+        transfers,
+        skipped,
+        remaining_balances: distribution.remaining_balances,
+    })?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    deps: DepsMut<NeutronQuery>,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
@@ -79,99 +503,2206 @@ pub fn execute(
                 [],
             ))
         }
-        ExecuteMsg::Distribute {} => execute_distribute(env, deps),
-        ExecuteMsg::SetTargetBalances { target_balances } => {
+        ExecuteMsg::Distribute {} => execute_distribute(env, deps, info),
+        ExecuteMsg::Deposit {} => execute_deposit(deps, info),
+        ExecuteMsg::SponsorDeposit {} => execute_sponsor_deposit(deps, info),
+        ExecuteMsg::SponsorWithdraw { denom, amount } => {
+            execute_sponsor_withdraw(deps, info, denom, amount)
+        }
+        ExecuteMsg::SetTargetBalances { target_balances } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::SetTargetBalances { target_balances },
+        ),
+        ExecuteMsg::AddTargetBalance { target_balance } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::AddTargetBalance { target_balance },
+        ),
+        ExecuteMsg::UpdateTargetBalance { target_balance } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::UpdateTargetBalance { target_balance },
+        ),
+        ExecuteMsg::RemoveTargetBalance { address, denom } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::RemoveTargetBalance { address, denom },
+        ),
+        ExecuteMsg::UpdateConfig { new_config } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::UpdateConfig { new_config },
+        ),
+        ExecuteMsg::AddCronSchedule { name, period } => {
+            execute_add_cron_schedule(deps, env, info, name, period)
+        }
+        ExecuteMsg::RemoveCronSchedule {} => execute_remove_cron_schedule(deps, info),
+        ExecuteMsg::SetRefillConfig { refill_config } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::SetRefillConfig { refill_config },
+        ),
+        ExecuteMsg::RemoveRefillConfig {} => execute_remove_refill_config(deps, info),
+        ExecuteMsg::SetFundingSource { funding_source } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::SetFundingSource { funding_source },
+        ),
+        ExecuteMsg::RemoveFundingSource {} => execute_remove_funding_source(deps, info),
+        // only treasurer with withdraw limits can move funds
+        ExecuteMsg::GrantRole {
+            role,
+            address,
+            withdraw_limits,
+        } if role == Role::Treasurer && !withdraw_limits.is_empty() => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::GrantRole {
+                role,
+                address,
+                withdraw_limits,
+            },
+        ),
+        ExecuteMsg::GrantRole {
+            role,
+            address,
+            withdraw_limits,
+        } => execute_grant_role(deps, info, role, address, withdraw_limits),
+        ExecuteMsg::RevokeRole { role, address } => execute_revoke_role(deps, info, role, address),
+        ExecuteMsg::Pause { withdrawals } => execute_pause(deps, env, info, withdrawals),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info),
+        ExecuteMsg::ProposeOperation { operation } => {
+            execute_propose_operation(deps, env, info, operation)
+        }
+        ExecuteMsg::CancelOperation { id } => execute_cancel_operation(deps, info, id),
+        ExecuteMsg::ExecuteOperation { id } => execute_pending_operation(deps, env, id),
+        ExecuteMsg::WithdrawTokens {
+            recepient,
+            amount,
+            denom,
+        } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::WithdrawTokens {
+                recepient,
+                amount,
+                denom,
+            },
+        ),
+        ExecuteMsg::WithdrawBalances { recepient, coins } => execute_untimelocked(
+            deps,
+            env,
+            info,
+            TimelockedOperation::WithdrawBalances { recepient, coins },
+        ),
+    }
+}
+
+/// Applies the operation right away, which is only allowed while timelock isn't configured
+fn execute_untimelocked(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    operation: TimelockedOperation,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let timelock = CONFIG
+        .may_load(deps.storage)?
+        .and_then(|config| config.timelock);
+    ensure!(timelock.is_none(), ContractError::TimelockRequired);
+    execute_operation(deps, env, info, operation)
+}
+
+fn execute_operation(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    operation: TimelockedOperation,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    match operation {
+        TimelockedOperation::WithdrawTokens {
+            recepient,
+            amount,
+            denom,
+        } => execute_withdraw_tokens(deps, info, env, amount, recepient, denom),
+        TimelockedOperation::WithdrawBalances { recepient, coins } => {
+            execute_withdraw_balances(deps, info, env, recepient, coins)
+        }
+        TimelockedOperation::SetTargetBalances { target_balances } => {
             execute_set_target_balances(deps, info, target_balances)
         }
-        ExecuteMsg::WithdrawTokens { recepient, amount } => {
-            execute_withdraw_tokens(deps, info, env, amount, recepient)
+        TimelockedOperation::AddTargetBalance { target_balance } => {
+            execute_add_target_balance(deps, info, target_balance)
+        }
+        TimelockedOperation::UpdateTargetBalance { target_balance } => {
+            execute_update_target_balance(deps, info, target_balance)
+        }
+        TimelockedOperation::RemoveTargetBalance { address, denom } => {
+            execute_remove_target_balance(deps, info, address, denom)
         }
+        TimelockedOperation::UpdateConfig { new_config } => {
+            execute_update_config(deps, info, new_config)
+        }
+        TimelockedOperation::SetRefillConfig { refill_config } => {
+            execute_set_refill_config(deps, info, refill_config)
+        }
+        TimelockedOperation::SetFundingSource { funding_source } => {
+            execute_set_funding_source(deps, info, funding_source)
+        }
+        TimelockedOperation::GrantRole {
+            role,
+            address,
+            withdraw_limits,
+        } => execute_grant_role(deps, info, role, address, withdraw_limits),
     }
 }
 
-fn execute_withdraw_tokens(
-    deps: DepsMut,
+fn execute_propose_operation(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    operation: TimelockedOperation,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    match operation {
+        TimelockedOperation::WithdrawTokens { .. }
+        | TimelockedOperation::WithdrawBalances { .. } => {
+            assert_owner_or_role(deps.storage, &info.sender, Role::Treasurer)?
+        }
+        TimelockedOperation::UpdateConfig { .. }
+        | TimelockedOperation::SetRefillConfig { .. }
+        | TimelockedOperation::SetFundingSource { .. }
+        | TimelockedOperation::GrantRole { .. } => {
+            cw_ownable::assert_owner(deps.storage, &info.sender)?
+        }
+        _ => assert_owner_or_role(deps.storage, &info.sender, Role::TargetManager)?,
+    }
+    let executable_at = match CONFIG.load(deps.storage)?.timelock {
+        Some(timelock) => timelock.after(&env.block),
+        None => Expiration::AtHeight(env.block.height),
+    };
+    let id = NEXT_OPERATION_ID
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    NEXT_OPERATION_ID.save(deps.storage, &(id + 1))?;
+    let attrs = [
+        attr("id", id.to_string()),
+        attr("operation", operation.name()),
+        attr("proposer", info.sender.to_string()),
+        attr("executable_at", executable_at.to_string()),
+    ];
+    PENDING_OPERATIONS.save(
+        deps.storage,
+        id,
+        &PendingOperation {
+            id,
+            operation,
+            proposer: info.sender,
+            executable_at,
+            proposed_height: env.block.height,
+            proposed_time: env.block.time,
+        },
+    )?;
+    Ok(response("execute-propose-operation", CONTRACT_NAME, attrs))
+}
+
+fn execute_cancel_operation(
+    deps: DepsMut<NeutronQuery>,
     info: MessageInfo,
+    id: u64,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let pending_operation = PENDING_OPERATIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::OperationNotFound { id })?;
+    // pausers are guardians, so they can cancel hostile operations as well
+    if pending_operation.proposer != info.sender {
+        assert_owner_or_role(deps.storage, &info.sender, Role::Pauser)?;
+    }
+    PENDING_OPERATIONS.remove(deps.storage, id);
+    Ok(response(
+        "execute-cancel-operation",
+        CONTRACT_NAME,
+        [
+            attr("id", id.to_string()),
+            attr("cancelled_by", info.sender),
+        ],
+    ))
+}
+
+fn execute_pending_operation(
+    deps: DepsMut<NeutronQuery>,
     env: Env,
-    amount: Option<Uint128>,
-    recepient: Option<String>,
+    id: u64,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let pending_operation = PENDING_OPERATIONS
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::OperationNotFound { id })?;
+    // timelock raised after the proposal delays the operation as well, lowered one doesn't
+    // bring it forward
+    let timelock_passed = match CONFIG.load(deps.storage)?.timelock {
+        Some(timelock) => duration_passed_since(
+            &timelock,
+            pending_operation.proposed_height,
+            pending_operation.proposed_time,
+            &env.block,
+        ),
+        None => true,
+    };
+    ensure!(
+        timelock_passed && pending_operation.executable_at.is_expired(&env.block),
+        ContractError::OperationNotReady { id }
+    );
+    PENDING_OPERATIONS.remove(deps.storage, id);
+    let operation_event = event(
+        "execute-operation",
+        CONTRACT_NAME,
+        [
+            attr("id", id.to_string()),
+            attr("operation", pending_operation.operation.name()),
+        ],
+    );
+    let info = MessageInfo {
+        sender: pending_operation.proposer,
+        funds: vec![],
+    };
+    Ok(execute_operation(deps, env, info, pending_operation.operation)?.add_event(operation_event))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(
+    _deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    msg: SudoMsg,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    match msg {
+        SudoMsg::Response { request, .. } => Ok(ibc_transfer_callback("sudo-response", request)),
+        SudoMsg::Error { request, details } => {
+            Ok(ibc_transfer_callback("sudo-error", request).add_attribute("details", details))
+        }
+        SudoMsg::Timeout { request } => Ok(ibc_transfer_callback("sudo-timeout", request)),
+    }
+}
+
+/// Failed transfers are refunded by IBC, so callbacks are only reported
+fn ibc_transfer_callback(ty: &str, request: RequestPacket) -> Response<NeutronMsg> {
+    response(
+        ty,
+        CONTRACT_NAME,
+        [
+            attr("channel", request.source_channel.unwrap_or_default()),
+            attr("sequence", request.sequence.unwrap_or_default().to_string()),
+        ],
+    )
+}
+
+fn execute_add_cron_schedule(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    period: u64,
 ) -> Result<Response<NeutronMsg>, ContractError> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
-    let contract_balance = deps
-        .querier
-        .query_balance(env.contract.address, UNTRN_DENOM.to_string())?
-        .amount;
-    let amount_to_send = amount.unwrap_or(contract_balance);
     ensure!(
-        amount_to_send <= contract_balance,
-        ContractError::InsufficientFunds
+        !CRON_SCHEDULE.exists(deps.storage),
+        ContractError::CronScheduleAlreadyExists
     );
-    let recepient = recepient.unwrap_or(info.sender.to_string());
+    CRON_SCHEDULE.save(
+        deps.storage,
+        &CronSchedule {
+            name: name.clone(),
+            period,
+        },
+    )?;
     Ok(response(
-        "execute-withdraw-tokens",
+        "execute-add-cron-schedule",
         CONTRACT_NAME,
-        Vec::<Attribute>::new(),
+        [
+            attr("name", name.clone()),
+            attr("period", period.to_string()),
+        ],
     )
-    .add_message(CosmosMsg::Bank(BankMsg::Send {
-        to_address: recepient,
-        amount: vec![Coin {
-            denom: UNTRN_DENOM.to_string(),
-            amount: amount_to_send,
+    .add_message(NeutronMsg::AddSchedule {
+        name,
+        period,
+        msgs: vec![MsgExecuteContract {
+            contract: env.contract.address.to_string(),
+            msg: to_json_string(&ExecuteMsg::Distribute {})?,
         }],
-    })))
+    }))
 }
 
-fn execute_set_target_balances(
-    deps: DepsMut,
+fn execute_remove_cron_schedule(
+    deps: DepsMut<NeutronQuery>,
     info: MessageInfo,
-    target_balances: Vec<TargetBalance>,
 ) -> Result<Response<NeutronMsg>, ContractError> {
     cw_ownable::assert_owner(deps.storage, &info.sender)?;
-    let attrs = target_balances
-        .clone()
-        .into_iter()
-        .map(|target_balance| {
-            deps.api.addr_validate(target_balance.address.as_str())?;
-            Ok(attr("set-target-balance", target_balance.address))
-        })
-        .collect::<StdResult<Vec<_>>>()?;
-    target_balances
-        .iter()
-        .for_each(|target_balance| target_balance.validate(deps.as_ref()));
-    TARGET_BALANCES.save(deps.storage, &target_balances)?;
+    let cron_schedule = CRON_SCHEDULE
+        .may_load(deps.storage)?
+        .ok_or(ContractError::CronScheduleNotFound)?;
+    CRON_SCHEDULE.remove(deps.storage);
+    Ok(response(
+        "execute-remove-cron-schedule",
+        CONTRACT_NAME,
+        [attr("name", cron_schedule.name.clone())],
+    )
+    .add_message(NeutronMsg::RemoveSchedule {
+        name: cron_schedule.name,
+    }))
+}
+
+fn execute_set_refill_config(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    refill_config: RefillConfig,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    refill_config.validate(deps.as_ref())?;
+    REFILL_CONFIG.save(deps.storage, &refill_config)?;
     Ok(response(
-        "execute-set-target-balances",
+        "execute-set-refill-config",
         CONTRACT_NAME,
-        attrs,
+        [
+            attr("router", refill_config.router),
+            attr("offer_denom", refill_config.offer_denom),
+            attr("offer_amount", refill_config.offer_amount),
+            attr("floor", refill_config.floor),
+            attr("max_slippage", refill_config.max_slippage.to_string()),
+            attr("min_price", refill_config.min_price.to_string()),
+            attr("cooldown", format!("{:?}", refill_config.cooldown)),
+        ],
     ))
 }
 
-fn execute_distribute(env: Env, deps: DepsMut) -> Result<Response<NeutronMsg>, ContractError> {
-    let mut attrs = vec![];
+fn execute_remove_refill_config(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    ensure!(
+        REFILL_CONFIG.exists(deps.storage),
+        ContractError::RefillConfigNotFound
+    );
+    REFILL_CONFIG.remove(deps.storage);
+    Ok(response::<(&str, &str), _>(
+        "execute-remove-refill-config",
+        CONTRACT_NAME,
+        [],
+    ))
+}
+
+fn execute_set_funding_source(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    funding_source: FundingSource,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    funding_source.validate(deps.as_ref())?;
+    FUNDING_SOURCE.save(deps.storage, &funding_source)?;
+    let source = match &funding_source.kind {
+        FundingSourceKind::Cw20Allowance { contract, .. } => contract,
+        FundingSourceKind::Treasury { contract, .. } => contract,
+    };
+    Ok(response(
+        "execute-set-funding-source",
+        CONTRACT_NAME,
+        [
+            attr("source", source.to_string()),
+            attr("amount", funding_source.amount),
+            attr("cap", funding_source.cap.amount),
+        ],
+    ))
+}
+
+fn execute_remove_funding_source(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    ensure!(
+        FUNDING_SOURCE.exists(deps.storage),
+        ContractError::FundingSourceNotFound
+    );
+    FUNDING_SOURCE.remove(deps.storage);
+    Ok(response::<(&str, &str), _>(
+        "execute-remove-funding-source",
+        CONTRACT_NAME,
+        [],
+    ))
+}
+
+fn execute_grant_role(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+    withdraw_limits: Vec<WithdrawLimit>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    ensure!(
+        role == Role::Treasurer || withdraw_limits.is_empty(),
+        ContractError::WithdrawLimitsNotAllowed
+    );
+    for withdraw_limit in &withdraw_limits {
+        ensure!(
+            !withdraw_limit.cap.amount.is_zero(),
+            ContractError::ZeroSpendingCap {
+                address: address.to_string(),
+                denom: withdraw_limit.denom.clone(),
+            }
+        );
+    }
+    let mut attrs = vec![
+        attr("role", role.key()),
+        attr("address", address.to_string()),
+    ];
+    if role == Role::Treasurer {
+        remove_treasurer_limits(deps.storage, &address)?;
+        for withdraw_limit in withdraw_limits {
+            attrs.push(attr(
+                "withdraw_limit",
+                format!("{}{}", withdraw_limit.cap.amount, withdraw_limit.denom),
+            ));
+            TREASURER_LIMITS.save(
+                deps.storage,
+                (&address, withdraw_limit.denom.as_str()),
+                &withdraw_limit.cap,
+            )?;
+        }
+    }
+    ROLES.save(deps.storage, (role.key(), &address), &Empty {})?;
+    Ok(response("execute-grant-role", CONTRACT_NAME, attrs))
+}
+
+fn execute_revoke_role(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    role: Role,
+    address: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let address = deps.api.addr_validate(&address)?;
+    ensure!(
+        ROLES.has(deps.storage, (role.key(), &address)),
+        ContractError::RoleNotGranted {
+            role: role.key().to_string(),
+            address: address.to_string(),
+        }
+    );
+    ROLES.remove(deps.storage, (role.key(), &address));
+    if role == Role::Treasurer {
+        remove_treasurer_limits(deps.storage, &address)?;
+    }
+    Ok(response(
+        "execute-revoke-role",
+        CONTRACT_NAME,
+        [attr("role", role.key()), attr("address", address)],
+    ))
+}
+
+fn execute_pause(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    info: MessageInfo,
+    withdrawals: bool,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::Pauser)?;
+    ensure!(!PAUSE.exists(deps.storage), ContractError::AlreadyPaused);
+    PAUSE.save(
+        deps.storage,
+        &PauseInfo {
+            paused_by: info.sender.clone(),
+            height: env.block.height,
+            time: env.block.time,
+            withdrawals_paused: withdrawals,
+        },
+    )?;
+    Ok(response(
+        "execute-pause",
+        CONTRACT_NAME,
+        [
+            attr("paused_by", info.sender),
+            attr("withdrawals_paused", withdrawals.to_string()),
+        ],
+    ))
+}
+
+fn execute_unpause(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::Pauser)?;
+    ensure!(PAUSE.exists(deps.storage), ContractError::NotPaused);
+    PAUSE.remove(deps.storage);
+    Ok(response(
+        "execute-unpause",
+        CONTRACT_NAME,
+        [attr("unpaused_by", info.sender)],
+    ))
+}
+
+/// Fails if withdrawals are paused
+fn assert_withdrawals_not_paused(storage: &dyn Storage) -> Result<(), ContractError> {
+    ensure!(
+        !PAUSE
+            .may_load(storage)?
+            .is_some_and(|pause| pause.withdrawals_paused),
+        ContractError::Paused
+    );
+    Ok(())
+}
+
+fn remove_treasurer_limits(storage: &mut dyn Storage, treasurer: &Addr) -> StdResult<()> {
+    let denoms = TREASURER_LIMITS
+        .prefix(treasurer)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in denoms {
+        TREASURER_LIMITS.remove(storage, (treasurer, denom.as_str()));
+    }
+    Ok(())
+}
+
+/// Passes if sender is the owner or holds the role, ownership error is returned otherwise
+fn assert_owner_or_role(
+    storage: &dyn Storage,
+    sender: &Addr,
+    role: Role,
+) -> Result<(), ContractError> {
+    if ROLES.has(storage, (role.key(), sender)) {
+        return Ok(());
+    }
+    cw_ownable::assert_owner(storage, sender)?;
+    Ok(())
+}
+
+fn execute_update_config(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    new_config: ConfigOptional,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    cw_ownable::assert_owner(deps.storage, &info.sender)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    let old_config = config.clone();
+    let attrs = update_config(deps.as_ref(), &mut config, new_config)?;
+    // without timelock nothing would delay withdrawing everything right after the change
+    ensure!(
+        old_config.timelock.is_some() || !withdraw_restrictions_loosened(&old_config, &config),
+        ContractError::WithdrawRestrictionsLoosened
+    );
+    CONFIG.save(deps.storage, &config)?;
+    // already registered balance queries keep their period unless they're updated
     let mut messages = vec![];
-    let mut contract_balance = deps
-        .querier
-        .query_balance(env.contract.address, UNTRN_DENOM.to_string())?
-        .amount;
-    for target_balance in TARGET_BALANCES.load(deps.storage)? {
-        let current_balance = deps
-            .querier
-            .query_balance(target_balance.address.clone(), UNTRN_DENOM.to_string())?
-            .amount;
-        if current_balance < target_balance.update_options.threshold_balance {
-            let funds_to_send = target_balance.update_options.target_balance - current_balance;
-            if contract_balance.checked_sub(funds_to_send).is_ok() {
-                messages.push(CosmosMsg::Bank(BankMsg::Send {
-                    to_address: target_balance.address.to_string(),
-                    amount: vec![Coin {
-                        denom: UNTRN_DENOM.to_string(),
-                        amount: funds_to_send,
-                    }],
-                }));
-                contract_balance = contract_balance.abs_diff(funds_to_send);
-                attrs.push(attr(target_balance.address.to_string(), funds_to_send));
-            }
-        }
-    }
-    Ok(response("execute-distribute", CONTRACT_NAME, attrs).add_messages(messages))
+    if config.balance_query_update_period != old_config.balance_query_update_period {
+        messages = REMOTE_BALANCE_QUERIES
+            .range(deps.storage, None, None, Order::Ascending)
+            .map(|item| {
+                let (_, query_id) = item?;
+                Ok(NeutronMsg::UpdateInterchainQuery {
+                    query_id,
+                    new_keys: None,
+                    new_update_period: Some(config.balance_query_update_period),
+                    new_transactions_filter: None,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+    Ok(response("execute-update-config", CONTRACT_NAME, attrs).add_messages(messages))
+}
+
+fn update_config(
+    deps: Deps<NeutronQuery>,
+    config: &mut Config,
+    new_config: ConfigOptional,
+) -> Result<Vec<Attribute>, ContractError> {
+    let mut attrs = vec![];
+    if let Some(shortfall_policy) = new_config.shortfall_policy {
+        attrs.push(attr("shortfall_policy", format!("{:?}", shortfall_policy)));
+        config.shortfall_policy = shortfall_policy;
+    }
+    if let Some(distribute_allowlist) = new_config.distribute_allowlist {
+        attrs.push(attr("distribute_allowlist", distribute_allowlist.join(",")));
+        config.distribute_allowlist = distribute_allowlist
+            .iter()
+            .map(|address| deps.api.addr_validate(address))
+            .collect::<StdResult<_>>()?;
+    }
+    if let Some(ibc_transfer_timeout) = new_config.ibc_transfer_timeout {
+        attrs.push(attr(
+            "ibc_transfer_timeout",
+            ibc_transfer_timeout.to_string(),
+        ));
+        config.ibc_transfer_timeout = ibc_transfer_timeout;
+    }
+    if let Some(balance_query_update_period) = new_config.balance_query_update_period {
+        attrs.push(attr(
+            "balance_query_update_period",
+            balance_query_update_period.to_string(),
+        ));
+        config.balance_query_update_period = balance_query_update_period;
+    }
+    if let Some(max_balance_age) = new_config.max_balance_age {
+        attrs.push(attr("max_balance_age", max_balance_age.to_string()));
+        config.max_balance_age = max_balance_age;
+    }
+    if let Some(accepted_denoms) = new_config.accepted_denoms {
+        attrs.push(attr("accepted_denoms", accepted_denoms.join(",")));
+        config.accepted_denoms = accepted_denoms;
+    }
+    if let Some(timelock) = new_config.timelock {
+        attrs.push(attr("timelock", format!("{:?}", timelock)));
+        config.timelock = match timelock {
+            Duration::Height(0) | Duration::Time(0) => None,
+            timelock => Some(timelock),
+        };
+    }
+    if let Some(withdraw_recipients) = new_config.withdraw_recipients {
+        attrs.push(attr("withdraw_recipients", withdraw_recipients.join(",")));
+        config.withdraw_recipients = withdraw_recipients
+            .iter()
+            .map(|recipient| {
+                deps.api.addr_validate(recipient).map_err(|_| {
+                    ContractError::InvalidWithdrawRecipient {
+                        recipient: recipient.clone(),
+                    }
+                })
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    if let Some(withdraw_limits) = new_config.withdraw_limits {
+        for withdraw_limit in &withdraw_limits {
+            ensure!(
+                !withdraw_limit.cap.amount.is_zero(),
+                ContractError::ZeroWithdrawCap {
+                    denom: withdraw_limit.denom.clone(),
+                }
+            );
+        }
+        attrs.push(attr(
+            "withdraw_limits",
+            withdraw_limits
+                .iter()
+                .map(|withdraw_limit| {
+                    format!("{}{}", withdraw_limit.cap.amount, withdraw_limit.denom)
+                })
+                .collect::<Vec<_>>()
+                .join(","),
+        ));
+        config.withdraw_limits = withdraw_limits;
+    }
+    Ok(attrs)
+}
+
+/// Whether `new` config allows withdrawals `old` one forbids: a recipient is added to or the
+/// allowlist is cleared, or some withdraw limit is dropped, raised or shortened
+fn withdraw_restrictions_loosened(old: &Config, new: &Config) -> bool {
+    let recipients_loosened = !old.withdraw_recipients.is_empty()
+        && (new.withdraw_recipients.is_empty()
+            || new
+                .withdraw_recipients
+                .iter()
+                .any(|recipient| !old.withdraw_recipients.contains(recipient)));
+    let limits_loosened = old.withdraw_limits.iter().any(|old_limit| {
+        let new_limit = new
+            .withdraw_limits
+            .iter()
+            .find(|withdraw_limit| withdraw_limit.denom == old_limit.denom);
+        match new_limit {
+            Some(new_limit) => {
+                let period_shortened = match (&new_limit.cap.period, &old_limit.cap.period) {
+                    (Duration::Height(new), Duration::Height(old))
+                    | (Duration::Time(new), Duration::Time(old)) => new < old,
+                    // periods in blocks and seconds can't be compared
+                    _ => true,
+                };
+                new_limit.cap.amount > old_limit.cap.amount || period_shortened
+            }
+            None => true,
+        }
+    });
+    recipients_loosened || limits_loosened
+}
+
+fn execute_withdraw_tokens(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    env: Env,
+    amount: Option<Uint128>,
+    recepient: Option<String>,
+    denom: Option<String>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let treasurer = authorize_withdrawal(deps.storage, &info.sender)?;
+    let denom = denom.unwrap_or(UNTRN_DENOM.to_string());
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let recepient = withdrawal_recipient(deps.as_ref(), &config, &info.sender, recepient)?;
+    let (_, message) = withdraw(
+        deps,
+        &env,
+        &config,
+        treasurer.as_ref(),
+        &recepient,
+        &denom,
+        amount,
+    )?;
+    Ok(response(
+        "execute-withdraw-tokens",
+        CONTRACT_NAME,
+        Vec::<Attribute>::new(),
+    )
+    .add_message(message))
+}
+
+/// Withdraws listed coins or, if `coins` aren't provided, every native balance of the contract
+fn execute_withdraw_balances(
+    mut deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    env: Env,
+    recepient: Option<String>,
+    coins: Option<Vec<Coin>>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let treasurer = authorize_withdrawal(deps.storage, &info.sender)?;
+    let config = CONFIG.may_load(deps.storage)?.unwrap_or_default();
+    let recepient = withdrawal_recipient(deps.as_ref(), &config, &info.sender, recepient)?;
+    let coins = match coins {
+        Some(coins) => {
+            // every coin is checked against the whole balance, so a denom can only be listed once
+            for (index, coin) in coins.iter().enumerate() {
+                ensure!(
+                    !coin.amount.is_zero(),
+                    ContractError::ZeroWithdrawAmount {
+                        denom: coin.denom.clone(),
+                    }
+                );
+                ensure!(
+                    !coins[..index].iter().any(|other| other.denom == coin.denom),
+                    ContractError::DuplicateWithdrawDenom {
+                        denom: coin.denom.clone(),
+                    }
+                );
+            }
+            coins
+        }
+        None => {
+            let mut coins = vec![];
+            for coin in deps
+                .querier
+                .query_all_balances(env.contract.address.clone())?
+            {
+                // funds of sponsor sub-accounts are left in place
+                let amount = coin
+                    .amount
+                    .saturating_sub(sponsored_balance(deps.storage, &coin.denom)?);
+                if !amount.is_zero() {
+                    coins.push(Coin {
+                        denom: coin.denom,
+                        amount,
+                    });
+                }
+            }
+            coins
+        }
+    };
+    ensure!(!coins.is_empty(), ContractError::InsufficientFunds);
+    let mut withdrawn = vec![];
+    let mut messages = vec![];
+    for coin in coins {
+        let (amount, message) = withdraw(
+            deps.branch(),
+            &env,
+            &config,
+            treasurer.as_ref(),
+            &recepient,
+            &coin.denom,
+            Some(coin.amount),
+        )?;
+        withdrawn.push(format!("{}{}", amount, coin.denom));
+        messages.push(message);
+    }
+    Ok(response(
+        "execute-withdraw-balances",
+        CONTRACT_NAME,
+        [
+            attr("recipient", recepient),
+            attr("amount", withdrawn.join(",")),
+        ],
+    )
+    .add_messages(messages))
+}
+
+/// Checks sender can withdraw tokens now, returns sender if it's a treasurer rather than the
+/// owner, so its withdraw limits apply
+fn authorize_withdrawal(
+    storage: &dyn Storage,
+    sender: &Addr,
+) -> Result<Option<Addr>, ContractError> {
+    let treasurer = if cw_ownable::is_owner(storage, sender)? {
+        None
+    } else {
+        assert_owner_or_role(storage, sender, Role::Treasurer)?;
+        Some(sender.clone())
+    };
+    assert_withdrawals_not_paused(storage)?;
+    Ok(treasurer)
+}
+
+/// Validates withdrawal recipient, sender is the recipient if it isn't provided
+fn withdrawal_recipient(
+    deps: Deps<NeutronQuery>,
+    config: &Config,
+    sender: &Addr,
+    recepient: Option<String>,
+) -> Result<Addr, ContractError> {
+    let recepient = match recepient {
+        Some(recepient) => deps.api.addr_validate(&recepient).map_err(|_| {
+            ContractError::InvalidWithdrawRecipient {
+                recipient: recepient.clone(),
+            }
+        })?,
+        None => sender.clone(),
+    };
+    ensure!(
+        config.withdraw_recipients.is_empty() || config.withdraw_recipients.contains(&recepient),
+        ContractError::WithdrawRecipientNotAllowed {
+            recipient: recepient.to_string(),
+        }
+    );
+    Ok(recepient)
+}
+
+/// Records the withdrawal against withdraw limits and returns withdrawn amount with transfer
+/// message. The whole shared balance in the denom is withdrawn if `amount` isn't provided
+fn withdraw(
+    deps: DepsMut<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+    treasurer: Option<&Addr>,
+    recepient: &Addr,
+    denom: &str,
+    amount: Option<Uint128>,
+) -> Result<(Uint128, CosmosMsg<NeutronMsg>), ContractError> {
+    // funds of sponsor sub-accounts can only be withdrawn by their sponsors
+    let shared_balance = query_token_balance(deps.as_ref(), &env.contract.address, denom)?
+        .saturating_sub(sponsored_balance(deps.storage, denom)?);
+    let amount = amount.unwrap_or(shared_balance);
+    ensure!(amount <= shared_balance, ContractError::InsufficientFunds);
+    if let Some(withdraw_limit) = config
+        .withdraw_limits
+        .iter()
+        .find(|withdraw_limit| withdraw_limit.denom == denom)
+    {
+        let mut history = WITHDRAW_HISTORY
+            .may_load(deps.storage, denom)?
+            .unwrap_or_default();
+        ensure!(
+            record_withdrawal(&mut history, &withdraw_limit.cap, &env.block, amount),
+            ContractError::WithdrawCapExceeded {
+                denom: denom.to_string(),
+            }
+        );
+        WITHDRAW_HISTORY.save(deps.storage, denom, &history)?;
+    }
+    if let Some(treasurer) = treasurer {
+        let key = (treasurer, denom);
+        let cap = TREASURER_LIMITS.may_load(deps.storage, key)?.ok_or(
+            ContractError::WithdrawLimitExceeded {
+                denom: denom.to_string(),
+            },
+        )?;
+        let mut history = TREASURER_WITHDRAWALS
+            .may_load(deps.storage, key)?
+            .unwrap_or_default();
+        ensure!(
+            record_withdrawal(&mut history, &cap, &env.block, amount),
+            ContractError::WithdrawLimitExceeded {
+                denom: denom.to_string(),
+            }
+        );
+        TREASURER_WITHDRAWALS.save(deps.storage, key, &history)?;
+    }
+    Ok((amount, transfer_msg(denom, recepient.to_string(), amount)?))
+}
+
+/// Adds the withdrawal to withdrawals within the current `cap` period unless it exceeds the cap
+fn record_withdrawal(
+    history: &mut Vec<TopUp>,
+    cap: &SpendingCap,
+    block: &BlockInfo,
+    amount: Uint128,
+) -> bool {
+    history.retain(|withdrawal| !duration_passed(&cap.period, withdrawal, block));
+    let withdrawn: Uint128 = history.iter().map(|withdrawal| withdrawal.amount).sum();
+    if withdrawn + amount > cap.amount {
+        return false;
+    }
+    history.push(TopUp {
+        height: block.height,
+        time: block.time,
+        amount,
+    });
+    true
+}
+
+fn execute_set_target_balances(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    target_balances: Vec<TargetBalance>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::TargetManager)?;
+    let attrs = target_balances
+        .iter()
+        .map(|target_balance| attr("set-target-balance", target_balance.address.clone()))
+        .collect::<Vec<_>>();
+    let messages = save_target_balances(deps, &target_balances)?;
+    Ok(response("execute-set-target-balances", CONTRACT_NAME, attrs).add_submessages(messages))
+}
+
+fn execute_add_target_balance(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    target_balance: TargetBalance,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::TargetManager)?;
+    target_balance.validate(deps.as_ref())?;
+    let address = target_address(deps.as_ref(), &target_balance)?;
+    let denom = target_balance.token.key();
+    let key = (&address, denom.as_str());
+    ensure!(
+        !TARGET_BALANCES.has(deps.storage, key),
+        ContractError::DuplicateTargetBalance {
+            address: target_balance.address.clone(),
+            denom: denom.clone(),
+        }
+    );
+    let messages = save_target_balance(deps.storage, key, &target_balance)?;
+    Ok(response(
+        "execute-add-target-balance",
+        CONTRACT_NAME,
+        [
+            attr("address", target_balance.address),
+            attr("denom", denom),
+        ],
+    )
+    .add_submessages(messages))
+}
+
+fn execute_update_target_balance(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    target_balance: TargetBalance,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::TargetManager)?;
+    target_balance.validate(deps.as_ref())?;
+    let address = target_address(deps.as_ref(), &target_balance)?;
+    let denom = target_balance.token.key();
+    let key = (&address, denom.as_str());
+    ensure!(
+        TARGET_BALANCES.has(deps.storage, key),
+        ContractError::UnknownTargetBalance
+    );
+    let messages = save_target_balance(deps.storage, key, &target_balance)?;
+    Ok(response(
+        "execute-update-target-balance",
+        CONTRACT_NAME,
+        [
+            attr("address", target_balance.address),
+            attr("denom", denom),
+        ],
+    )
+    .add_submessages(messages))
+}
+
+fn execute_remove_target_balance(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    address: String,
+    denom: String,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_owner_or_role(deps.storage, &info.sender, Role::TargetManager)?;
+    // remote targets are stored unvalidated, so the key is looked up as is
+    let target_address = Addr::unchecked(&address);
+    let key = (&target_address, denom.as_str());
+    ensure!(
+        TARGET_BALANCES.has(deps.storage, key),
+        ContractError::UnknownTargetBalance
+    );
+    TARGET_BALANCES.remove(deps.storage, key);
+    REMOTE_TARGETS.remove(deps.storage, key);
+    remove_top_ups(deps.storage, key);
+    let message = remove_balance_query(deps.storage, key)?;
+    Ok(response(
+        "execute-remove-target-balance",
+        CONTRACT_NAME,
+        [attr("address", address), attr("denom", denom)],
+    )
+    .add_messages(message))
+}
+
+fn execute_distribute(
+    env: Env,
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    // only the allowlist restricts distribution, distributors are allowed on top of it
+    ensure!(
+        config.distribute_allowlist.is_empty()
+            || config.distribute_allowlist.contains(&info.sender)
+            || ROLES.has(deps.storage, (Role::Distributor.key(), &info.sender))
+            || cw_ownable::is_owner(deps.storage, &info.sender)?,
+        ContractError::DistributeNotAllowed
+    );
+    ensure!(!PAUSE.exists(deps.storage), ContractError::Paused);
+    let distribution = plan_distribution(deps.as_ref(), &env)?;
+    let refill = plan_refill(deps.as_ref(), &env, &distribution)?;
+    let pull = plan_pull(deps.as_ref(), &env)?;
+    let mut attrs = vec![attr(
+        "funded_targets",
+        distribution
+            .targets
+            .iter()
+            .filter(|target| !target.amount.is_zero())
+            .count()
+            .to_string(),
+    )];
+    for (sent, remaining) in distribution
+        .total_sent
+        .iter()
+        .zip(distribution.remaining_balances.iter())
+    {
+        attrs.push(attr("total_sent", sent.to_string()));
+        attrs.push(attr("remaining_balance", remaining.to_string()));
+    }
+    let mut events = vec![];
+    let mut messages = vec![];
+    for target in distribution.targets {
+        events.push(event(
+            "distribute-target",
+            CONTRACT_NAME,
+            [
+                attr("address", target.address.to_string()),
+                attr("denom", target.denom.clone()),
+                attr("outcome", target.outcome().to_string()),
+                attr("balance", target.current_balance),
+                attr("deficit", target.deficit),
+                attr("amount", target.amount),
+            ],
+        ));
+        if !target.amount.is_zero() {
+            let key = (&target.address, target.denom.as_str());
+            let top_up = TopUp {
+                height: env.block.height,
+                time: env.block.time,
+                amount: target.amount,
+            };
+            LAST_TOP_UPS.save(deps.storage, key, &top_up)?;
+            if let Some(spending_cap) = &target.spending_cap {
+                let mut history = TOP_UP_HISTORY
+                    .may_load(deps.storage, key)?
+                    .unwrap_or_default();
+                history.retain(|top_up| !duration_passed(&spending_cap.period, top_up, &env.block));
+                history.push(top_up);
+                TOP_UP_HISTORY.save(deps.storage, key, &history)?;
+            }
+            if let Some(sponsor) = &target.sponsor {
+                let key = (sponsor, target.denom.as_str());
+                SPONSOR_BALANCES.update(deps.storage, key, |balance| -> StdResult<_> {
+                    Ok(balance.unwrap_or_default().checked_sub(target.amount)?)
+                })?;
+                SPONSORED_TOTALS.update(deps.storage, &target.denom, |total| -> StdResult<_> {
+                    Ok(total.unwrap_or_default().checked_sub(target.amount)?)
+                })?;
+                SPONSOR_SPENT.update(deps.storage, key, |spent| -> StdResult<_> {
+                    Ok(spent.unwrap_or_default() + target.amount)
+                })?;
+            }
+            messages.push(match target.remote {
+                Some(remote) => CosmosMsg::Custom(NeutronMsg::IbcTransfer {
+                    source_port: "transfer".to_string(),
+                    source_channel: remote.channel,
+                    token: Coin {
+                        denom: target.denom,
+                        amount: target.amount,
+                    },
+                    sender: env.contract.address.to_string(),
+                    receiver: target.address.to_string(),
+                    timeout_height: RequestPacketTimeoutHeight {
+                        revision_number: None,
+                        revision_height: None,
+                    },
+                    timeout_timestamp: env
+                        .block
+                        .time
+                        .plus_seconds(config.ibc_transfer_timeout)
+                        .nanos(),
+                    memo: "".to_string(),
+                    // planner only funds remote targets once the fee is known
+                    fee: distribution.ibc_fee.clone().unwrap_or(IbcFee {
+                        recv_fee: vec![],
+                        ack_fee: vec![],
+                        timeout_fee: vec![],
+                    }),
+                }),
+                None => transfer_msg(&target.denom, target.address.to_string(), target.amount)?,
+            });
+        }
+    }
+    let mut pull_message = None;
+    if let Some((pull_msg, pull_event, pending_pull)) = pull {
+        PENDING_PULL.save(deps.storage, &pending_pull)?;
+        events.push(pull_event);
+        // failed pull is only reported in reply, so it never blocks distribution
+        pull_message = Some(SubMsg::reply_always(pull_msg, PULL_REPLY_ID));
+    }
+    if let Some((swap_msg, swap_event, swap)) = refill {
+        LAST_REFILL.save(deps.storage, &swap)?;
+        events.push(swap_event);
+        messages.push(CosmosMsg::Wasm(swap_msg));
+    }
+    // pull goes first, so transfers don't change the balance its received amount is measured by
+    Ok(response("execute-distribute", CONTRACT_NAME, attrs)
+        .add_submessages(pull_message)
+        .add_events(events)
+        .add_messages(messages))
+}
+
+fn execute_deposit(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    validate_deposit_funds(deps.as_ref(), &info.funds)?;
+    for coin in &info.funds {
+        DEPOSITS.update(
+            deps.storage,
+            (&info.sender, coin.denom.as_str()),
+            |total| -> StdResult<_> { Ok(total.unwrap_or_default() + coin.amount) },
+        )?;
+    }
+    Ok(response(
+        "execute-deposit",
+        CONTRACT_NAME,
+        [
+            attr("depositor", info.sender.to_string()),
+            attr(
+                "amount",
+                info.funds
+                    .iter()
+                    .map(|coin| coin.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+        ],
+    ))
+}
+
+fn execute_sponsor_deposit(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    validate_deposit_funds(deps.as_ref(), &info.funds)?;
+    for coin in &info.funds {
+        SPONSOR_BALANCES.update(
+            deps.storage,
+            (&info.sender, coin.denom.as_str()),
+            |balance| -> StdResult<_> { Ok(balance.unwrap_or_default() + coin.amount) },
+        )?;
+        SPONSORED_TOTALS.update(deps.storage, &coin.denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + coin.amount)
+        })?;
+    }
+    Ok(response(
+        "execute-sponsor-deposit",
+        CONTRACT_NAME,
+        [
+            attr("sponsor", info.sender.to_string()),
+            attr(
+                "amount",
+                info.funds
+                    .iter()
+                    .map(|coin| coin.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+        ],
+    ))
+}
+
+fn execute_sponsor_withdraw(
+    deps: DepsMut<NeutronQuery>,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    assert_withdrawals_not_paused(deps.storage)?;
+    let key = (&info.sender, denom.as_str());
+    let balance = SPONSOR_BALANCES
+        .may_load(deps.storage, key)?
+        .unwrap_or_default();
+    let amount = amount.unwrap_or(balance);
+    ensure!(
+        !amount.is_zero() && amount <= balance,
+        ContractError::InsufficientFunds
+    );
+    SPONSOR_BALANCES.save(deps.storage, key, &(balance - amount))?;
+    SPONSORED_TOTALS.update(deps.storage, &denom, |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default().checked_sub(amount)?)
+    })?;
+    Ok(response(
+        "execute-sponsor-withdraw",
+        CONTRACT_NAME,
+        [
+            attr("sponsor", info.sender.to_string()),
+            attr("denom", denom.clone()),
+            attr("amount", amount),
+        ],
+    )
+    .add_message(transfer_msg(&denom, info.sender.to_string(), amount)?))
+}
+
+/// Checks funds sent along with `Deposit` and `SponsorDeposit`
+fn validate_deposit_funds(deps: Deps<NeutronQuery>, funds: &[Coin]) -> Result<(), ContractError> {
+    ensure!(!funds.is_empty(), ContractError::NoFunds);
+    let config = CONFIG.load(deps.storage)?;
+    for coin in funds {
+        ensure!(
+            config.accepted_denoms.is_empty() || config.accepted_denoms.contains(&coin.denom),
+            ContractError::DenomNotAccepted {
+                denom: coin.denom.clone(),
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Total balance of sponsor sub-accounts in given denom, it's not available to other targets
+fn sponsored_balance(storage: &dyn Storage, denom: &str) -> StdResult<Uint128> {
+    Ok(SPONSORED_TOTALS
+        .may_load(storage, denom)?
+        .unwrap_or_default())
+}
+
+enum DistributionOutcome {
+    Funded,
+    PartiallyFunded,
+    Skipped(SkipReason),
+}
+
+impl std::fmt::Display for DistributionOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DistributionOutcome::Funded => write!(f, "funded"),
+            DistributionOutcome::PartiallyFunded => write!(f, "partially_funded"),
+            DistributionOutcome::Skipped(SkipReason::Healthy) => write!(f, "healthy"),
+            DistributionOutcome::Skipped(SkipReason::InsufficientFunds) => {
+                write!(f, "insufficient_funds")
+            }
+            DistributionOutcome::Skipped(SkipReason::Cooldown) => write!(f, "cooldown"),
+            DistributionOutcome::Skipped(SkipReason::SpendingCapReached) => {
+                write!(f, "spending_cap_reached")
+            }
+            DistributionOutcome::Skipped(SkipReason::BalanceUnavailable) => {
+                write!(f, "balance_unavailable")
+            }
+            DistributionOutcome::Skipped(SkipReason::StaleBalance) => write!(f, "stale_balance"),
+        }
+    }
+}
+
+struct PlannedTarget {
+    address: Addr,
+    denom: String,
+    priority: u32,
+    current_balance: Uint128,
+    deficit: Uint128,
+    /// Reason target can't be funded regardless of contract balance
+    blocked_by: Option<SkipReason>,
+    spending_cap: Option<SpendingCap>,
+    /// Amount target can still receive within its spending cap period, `None` if not capped
+    allowance: Option<Uint128>,
+    remote: Option<RemoteTarget>,
+    /// Sponsor paying for the target, shared contract balance is used if `None`
+    sponsor: Option<Addr>,
+    amount: Uint128,
+}
+
+impl PlannedTarget {
+    /// Deficit that can be covered in this distribution
+    fn fundable_deficit(&self) -> Uint128 {
+        match (&self.blocked_by, self.allowance) {
+            (Some(_), _) => Uint128::zero(),
+            (None, Some(allowance)) => self.deficit.min(allowance),
+            (None, None) => self.deficit,
+        }
+    }
+
+    fn outcome(&self) -> DistributionOutcome {
+        if let Some(reason @ (SkipReason::BalanceUnavailable | SkipReason::StaleBalance)) =
+            &self.blocked_by
+        {
+            // balance is unknown, so target can't be reported as healthy
+            DistributionOutcome::Skipped(reason.clone())
+        } else if self.deficit.is_zero() {
+            DistributionOutcome::Skipped(SkipReason::Healthy)
+        } else if let Some(reason) = &self.blocked_by {
+            DistributionOutcome::Skipped(reason.clone())
+        } else if self.amount == self.deficit {
+            DistributionOutcome::Funded
+        } else if self.amount.is_zero() {
+            DistributionOutcome::Skipped(SkipReason::InsufficientFunds)
+        } else {
+            DistributionOutcome::PartiallyFunded
+        }
+    }
+}
+
+struct Distribution {
+    /// Targets grouped by denom and sponsor, sorted by descending priority within every group
+    targets: Vec<PlannedTarget>,
+    /// Amounts sent in every processed denom, in the same order as `remaining_balances`
+    total_sent: Vec<Coin>,
+    remaining_balances: Vec<Coin>,
+    /// Fee paid for every IBC transfer, only queried when some remote target can be funded
+    ibc_fee: Option<IbcFee>,
+    /// Total `untrn` paid in IBC fees of funded remote targets
+    ibc_fees: Uint128,
+}
+
+impl Distribution {
+    /// Contract balance in given denom left after distribution
+    fn remaining_balance(
+        &self,
+        deps: Deps<NeutronQuery>,
+        env: &Env,
+        denom: &str,
+    ) -> StdResult<Uint128> {
+        if let Some(coin) = self
+            .remaining_balances
+            .iter()
+            .find(|coin| coin.denom == denom)
+        {
+            return Ok(coin.amount);
+        }
+        let balance = query_token_balance(deps, &env.contract.address, denom)?;
+        Ok(if denom == UNTRN_DENOM {
+            balance.saturating_sub(self.ibc_fees)
+        } else {
+            balance
+        })
+    }
+
+    /// Contract balance in given denom left after distribution, without funds still held by
+    /// sponsor sub-accounts
+    fn remaining_shared_balance(
+        &self,
+        deps: Deps<NeutronQuery>,
+        env: &Env,
+        denom: &str,
+    ) -> StdResult<Uint128> {
+        let sponsored_sent: Uint128 = self
+            .targets
+            .iter()
+            .filter(|target| target.sponsor.is_some() && target.denom == denom)
+            .map(|target| target.amount)
+            .sum();
+        let sponsored = sponsored_balance(deps.storage, denom)?.saturating_sub(sponsored_sent);
+        Ok(self
+            .remaining_balance(deps, env, denom)?
+            .saturating_sub(sponsored))
+    }
+}
+
+fn plan_distribution(deps: Deps<NeutronQuery>, env: &Env) -> Result<Distribution, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut targets_by_denom: BTreeMap<String, Vec<PlannedTarget>> = BTreeMap::new();
+    for item in TARGET_BALANCES.range(deps.storage, None, None, Order::Ascending) {
+        let ((address, denom), update_options) = item?;
+        let target = plan_target(deps, env, &config, address, denom, update_options)?;
+        targets_by_denom
+            .entry(target.denom.clone())
+            .or_default()
+            .push(target);
+    }
+    let mut distribution = Distribution {
+        targets: vec![],
+        total_sent: vec![],
+        remaining_balances: vec![],
+        ibc_fee: None,
+        ibc_fees: Uint128::zero(),
+    };
+    // every remote target that may get funds reserves an IBC fee in untrn upfront
+    let mut fee_per_transfer = Uint128::zero();
+    let mut fee_reserve = Uint128::zero();
+    let fundable_remote_targets = targets_by_denom
+        .values()
+        .flatten()
+        .filter(|target| target.remote.is_some() && !target.fundable_deficit().is_zero())
+        .count();
+    if fundable_remote_targets > 0 {
+        let ibc_fee = deps
+            .querier
+            .query::<MinIbcFeeResponse>(&QueryRequest::Custom(NeutronQuery::MinIbcFee {}))?
+            .min_fee;
+        fee_per_transfer = ibc_fee
+            .ack_fee
+            .iter()
+            .chain(ibc_fee.timeout_fee.iter())
+            .filter(|coin| coin.denom == UNTRN_DENOM)
+            .map(|coin| coin.amount)
+            .sum();
+        fee_reserve = fee_per_transfer * Uint128::from(fundable_remote_targets as u128);
+        // IBC fees are always paid from the shared balance
+        let untrn_balance = deps
+            .querier
+            .query_balance(env.contract.address.clone(), UNTRN_DENOM)?
+            .amount
+            .saturating_sub(sponsored_balance(deps.storage, UNTRN_DENOM)?);
+        if fee_reserve > untrn_balance {
+            for target in targets_by_denom.values_mut().flatten() {
+                if target.remote.is_some() && target.blocked_by.is_none() {
+                    target.blocked_by = Some(SkipReason::InsufficientFunds);
+                }
+            }
+            fee_reserve = Uint128::zero();
+        } else {
+            distribution.ibc_fee = Some(ibc_fee);
+        }
+    }
+    for (denom, mut targets) in targets_by_denom {
+        // stable sort keeps targets with equal priority in storage order
+        targets.sort_by(|a, b| b.priority.cmp(&a.priority));
+        // contract balance can't be read from a broken cw20 token either, its targets are
+        // skipped with unavailable balance anyway
+        let contract_balance =
+            query_token_balance(deps, &env.contract.address, &denom).unwrap_or_default();
+        let shared_balance =
+            contract_balance.saturating_sub(sponsored_balance(deps.storage, &denom)?);
+        // sponsored targets are funded from their sponsor's sub-account only, `None` goes first
+        // so targets paid from the shared balance keep their order
+        let mut pools: BTreeMap<Option<Addr>, Vec<PlannedTarget>> = BTreeMap::new();
+        for target in targets {
+            pools
+                .entry(target.sponsor.clone())
+                .or_default()
+                .push(target);
+        }
+        let mut total_sent = Uint128::zero();
+        // sub-accounts may add up to more than the contract holds after an out-of-band
+        // drain, so every pool is capped by what earlier pools left
+        let mut remaining_balance = contract_balance;
+        for (sponsor, mut targets) in pools {
+            let available = match sponsor {
+                Some(sponsor) => SPONSOR_BALANCES
+                    .may_load(deps.storage, (&sponsor, denom.as_str()))?
+                    .unwrap_or_default(),
+                None if denom == UNTRN_DENOM => shared_balance.saturating_sub(fee_reserve),
+                None => shared_balance,
+            }
+            .min(remaining_balance);
+            let allocations = allocate_funds(&config.shortfall_policy, available, &targets);
+            for (target, amount) in targets.iter_mut().zip(allocations) {
+                target.amount = amount;
+                total_sent += amount;
+                remaining_balance = remaining_balance.saturating_sub(amount);
+            }
+            distribution.targets.extend(targets);
+        }
+        distribution.total_sent.push(Coin {
+            denom: denom.clone(),
+            amount: total_sent,
+        });
+        distribution.remaining_balances.push(Coin {
+            denom,
+            amount: remaining_balance,
+        });
+    }
+    let funded_remote_targets = distribution
+        .targets
+        .iter()
+        .filter(|target| target.remote.is_some() && !target.amount.is_zero())
+        .count();
+    distribution.ibc_fees = fee_per_transfer * Uint128::from(funded_remote_targets as u128);
+    if let Some(untrn) = distribution
+        .remaining_balances
+        .iter_mut()
+        .find(|coin| coin.denom == UNTRN_DENOM)
+    {
+        untrn.amount = untrn.amount.saturating_sub(distribution.ibc_fees);
+    }
+    Ok(distribution)
+}
+
+/// Target state that doesn't depend on other targets, nothing is allocated to it yet
+fn plan_target(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    config: &Config,
+    address: Addr,
+    denom: String,
+    update_options: TargetBalanceUpdateParams,
+) -> StdResult<PlannedTarget> {
+    let remote = REMOTE_TARGETS.may_load(deps.storage, (&address, denom.as_str()))?;
+    let last_top_up = LAST_TOP_UPS.may_load(deps.storage, (&address, denom.as_str()))?;
+    let mut data_issue = None;
+    let current_balance = match &remote {
+        Some(remote) => {
+            match query_remote_balance(deps, env, (&address, denom.as_str()), remote)? {
+                Some((_, height))
+                    if is_stale_balance(config, &env.block, height, last_top_up.as_ref()) =>
+                {
+                    data_issue = Some(SkipReason::StaleBalance);
+                    Uint128::zero()
+                }
+                Some((amount, _)) => amount,
+                None => {
+                    data_issue = Some(SkipReason::BalanceUnavailable);
+                    Uint128::zero()
+                }
+            }
+        }
+        // cw20 token contract may be wrong or broken, it mustn't block other targets
+        None => match query_token_balance(deps, &address, &denom) {
+            Ok(balance) => balance,
+            Err(_) => {
+                data_issue = Some(SkipReason::BalanceUnavailable);
+                Uint128::zero()
+            }
+        },
+    };
+    let deficit = if current_balance < update_options.threshold_balance {
+        update_options.target_balance - current_balance
+    } else {
+        Uint128::zero()
+    };
+    let mut blocked_by = None;
+    if let (Some(cooldown), Some(last_top_up)) = (update_options.cooldown, &last_top_up) {
+        if !duration_passed(&cooldown, last_top_up, &env.block) {
+            blocked_by = Some(SkipReason::Cooldown);
+        }
+    }
+    let allowance = match &update_options.spending_cap {
+        Some(spending_cap) => {
+            let spent = spent_within_period(deps, &env.block, &address, &denom, spending_cap)?;
+            Some(spending_cap.amount.saturating_sub(spent))
+        }
+        None => None,
+    };
+    if blocked_by.is_none() && allowance == Some(Uint128::zero()) {
+        blocked_by = Some(SkipReason::SpendingCapReached);
+    }
+    if data_issue.is_some() {
+        blocked_by = data_issue;
+    }
+    Ok(PlannedTarget {
+        address,
+        denom,
+        priority: update_options.priority,
+        current_balance,
+        deficit,
+        blocked_by,
+        spending_cap: update_options.spending_cap,
+        allowance,
+        remote,
+        sponsor: update_options.sponsor,
+        amount: Uint128::zero(),
+    })
+}
+
+/// Swap of the treasury asset into `untrn` if refill is configured, its cooldown has passed and
+/// `untrn` left after distribution is below the floor. Swapped amount is only available for the
+/// next distribution
+fn plan_refill(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    distribution: &Distribution,
+) -> Result<Option<(WasmMsg, Event, TopUp)>, ContractError> {
+    let refill_config = match REFILL_CONFIG.may_load(deps.storage)? {
+        Some(refill_config) => refill_config,
+        None => return Ok(None),
+    };
+    if let Some(last_refill) = LAST_REFILL.may_load(deps.storage)? {
+        if !duration_passed(&refill_config.cooldown, &last_refill, &env.block) {
+            return Ok(None);
+        }
+    }
+    // funds of sponsor sub-accounts are neither counted towards the floor nor swapped
+    let untrn_balance = distribution.remaining_shared_balance(deps, env, UNTRN_DENOM)?;
+    if untrn_balance >= refill_config.floor {
+        return Ok(None);
+    }
+    let offer_amount = distribution
+        .remaining_shared_balance(deps, env, &refill_config.offer_denom)?
+        .min(refill_config.offer_amount);
+    if offer_amount.is_zero() {
+        return Ok(None);
+    }
+    let operations = vec![SwapOperation::AstroSwap {
+        offer_asset_info: AssetInfo::NativeToken {
+            denom: refill_config.offer_denom.clone(),
+        },
+        ask_asset_info: AssetInfo::NativeToken {
+            denom: UNTRN_DENOM.to_string(),
+        },
+    }];
+    // refill must never block distribution, so swap is skipped if router can't price it
+    let simulation: SimulateSwapOperationsResponse = match deps.querier.query_wasm_smart(
+        refill_config.router.clone(),
+        &RouterQueryMsg::SimulateSwapOperations {
+            offer_amount,
+            operations: operations.clone(),
+        },
+    ) {
+        Ok(simulation) => simulation,
+        Err(_) => return Ok(None),
+    };
+    // simulation can't detect a pool moved within the same transaction, so swap is skipped
+    // rather than made below the configured price
+    let price_bound = offer_amount * refill_config.min_price;
+    if simulation.amount < price_bound {
+        return Ok(None);
+    }
+    let minimum_receive =
+        (simulation.amount * (Decimal::one() - refill_config.max_slippage)).max(price_bound);
+    let offer = Coin {
+        denom: refill_config.offer_denom,
+        amount: offer_amount,
+    };
+    let swap_event = event(
+        "refill-swap",
+        CONTRACT_NAME,
+        [
+            attr("router", refill_config.router.to_string()),
+            attr("offer", offer.to_string()),
+            attr("simulated_amount", simulation.amount),
+            attr("minimum_receive", minimum_receive),
+            attr("untrn_balance", untrn_balance),
+        ],
+    );
+    let swap_msg = WasmMsg::Execute {
+        contract_addr: refill_config.router.to_string(),
+        msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+            operations,
+            minimum_receive: Some(minimum_receive),
+            to: None,
+            max_spread: Some(refill_config.max_slippage),
+        })?,
+        funds: vec![offer],
+    };
+    let swap = TopUp {
+        height: env.block.height,
+        time: env.block.time,
+        amount: offer_amount,
+    };
+    Ok(Some((swap_msg, swap_event, swap)))
+}
+
+/// Splits available funds between deficits of targets sorted by descending priority
+fn allocate_funds(
+    shortfall_policy: &ShortfallPolicy,
+    mut available: Uint128,
+    targets: &[PlannedTarget],
+) -> Vec<Uint128> {
+    let mut allocations = Vec::with_capacity(targets.len());
+    match shortfall_policy {
+        ShortfallPolicy::Skip => {
+            for target in targets {
+                let deficit = target.fundable_deficit();
+                if deficit <= available {
+                    available -= deficit;
+                    allocations.push(deficit);
+                } else {
+                    allocations.push(Uint128::zero());
+                }
+            }
+        }
+        ShortfallPolicy::PartialTopUp => {
+            for target in targets {
+                let amount = target.fundable_deficit().min(available);
+                available -= amount;
+                allocations.push(amount);
+            }
+        }
+        ShortfallPolicy::ProRata => {
+            let mut tier_start = 0;
+            while tier_start < targets.len() {
+                let priority = targets[tier_start].priority;
+                let tier_end = tier_start
+                    + targets[tier_start..]
+                        .iter()
+                        .take_while(|target| target.priority == priority)
+                        .count();
+                let tier = &targets[tier_start..tier_end];
+                let total: Uint128 = tier.iter().map(|target| target.fundable_deficit()).sum();
+                if total <= available {
+                    available -= total;
+                    allocations.extend(tier.iter().map(|target| target.fundable_deficit()));
+                } else {
+                    allocations.extend(
+                        tier.iter().map(|target| {
+                            target.fundable_deficit().multiply_ratio(available, total)
+                        }),
+                    );
+                    // lower tiers get nothing once a higher one couldn't be covered in full
+                    available = Uint128::zero();
+                }
+                tier_start = tier_end;
+            }
+        }
+    }
+    allocations
+}
+
+/// Balance of the remote target read from its interchain query along with local height the
+/// result was submitted at. `None` if the query isn't registered or has no result yet
+fn query_remote_balance(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+    key: (&Addr, &str),
+    remote: &RemoteTarget,
+) -> StdResult<Option<(Uint128, u64)>> {
+    let query_id = match REMOTE_BALANCE_QUERIES.may_load(deps.storage, key)? {
+        Some(query_id) => query_id,
+        None => return Ok(None),
+    };
+    Ok(query_balance(deps, env.clone(), query_id)
+        .ok()
+        .map(|response| {
+            let amount = response
+                .balances
+                .coins
+                .iter()
+                .find(|coin| coin.denom == remote.remote_denom)
+                .map(|coin| coin.amount)
+                .unwrap_or_default();
+            (amount, response.last_submitted_local_height)
+        }))
+}
+
+/// Pull from the funding source if it's configured and its cap isn't reached, along with the
+/// pulled token key and contract balance in it before the pull. Pulled funds are only available
+/// for the next distribution
+fn plan_pull(
+    deps: Deps<NeutronQuery>,
+    env: &Env,
+) -> Result<Option<(WasmMsg, Event, (String, Uint128))>, ContractError> {
+    let funding_source = match FUNDING_SOURCE.may_load(deps.storage)? {
+        Some(funding_source) => funding_source,
+        None => return Ok(None),
+    };
+    let pulled: Uint128 = PULL_HISTORY
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        .iter()
+        .filter(|pull| !duration_passed(&funding_source.cap.period, pull, &env.block))
+        .map(|pull| pull.amount)
+        .sum();
+    let amount = funding_source
+        .amount
+        .min(funding_source.cap.amount.saturating_sub(pulled));
+    let (source, denom, amount, msg) = match funding_source.kind {
+        FundingSourceKind::Cw20Allowance { contract, owner } => {
+            // pull must never block distribution, so it's skipped if allowance can't be read
+            let allowance: AllowanceResponse = match deps.querier.query_wasm_smart(
+                contract.clone(),
+                &Cw20QueryMsg::Allowance {
+                    owner: owner.to_string(),
+                    spender: env.contract.address.to_string(),
+                },
+            ) {
+                Ok(allowance) => allowance,
+                Err(_) => return Ok(None),
+            };
+            let allowance = if allowance.expires.is_expired(&env.block) {
+                Uint128::zero()
+            } else {
+                allowance.allowance
+            };
+            let amount = amount.min(allowance);
+            let msg = to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                owner: owner.to_string(),
+                recipient: env.contract.address.to_string(),
+                amount,
+            })?;
+            let denom = Token::Cw20 {
+                contract: contract.clone(),
+            }
+            .key();
+            (contract, denom, amount, msg)
+        }
+        FundingSourceKind::Treasury { contract, denom } => {
+            let msg = to_json_binary(&TreasuryExecuteMsg::Withdraw {
+                recipient: env.contract.address.to_string(),
+                amount: Coin {
+                    denom: denom.clone(),
+                    amount,
+                },
+            })?;
+            (contract, denom, amount, msg)
+        }
+    };
+    if amount.is_zero() {
+        return Ok(None);
+    }
+    // received amount is measured against this balance in reply
+    let balance = match query_token_balance(deps, &env.contract.address, &denom) {
+        Ok(balance) => balance,
+        Err(_) => return Ok(None),
+    };
+    let pull_event = event(
+        "pull-funds",
+        CONTRACT_NAME,
+        [
+            attr("source", source.to_string()),
+            attr("denom", denom.clone()),
+            attr("amount", amount),
+        ],
+    );
+    let pull_msg = WasmMsg::Execute {
+        contract_addr: source.to_string(),
+        msg,
+        funds: vec![],
+    };
+    Ok(Some((pull_msg, pull_event, (denom, balance))))
+}
+
+/// Whether remote balance submitted at given local height can't be trusted. Result may be too
+/// old or not include the last transfer yet
+fn is_stale_balance(
+    config: &Config,
+    block: &BlockInfo,
+    last_submitted_height: u64,
+    last_top_up: Option<&TopUp>,
+) -> bool {
+    block.height.saturating_sub(last_submitted_height) > config.max_balance_age
+        || last_top_up.is_some_and(|top_up| last_submitted_height <= top_up.height)
+}
+
+/// Balance of the address in the token keyed by `denom`, see `Token::key`
+fn query_token_balance(
+    deps: Deps<NeutronQuery>,
+    address: &Addr,
+    denom: &str,
+) -> StdResult<Uint128> {
+    match Token::from_key(denom) {
+        Token::Native { denom } => Ok(deps.querier.query_balance(address, denom)?.amount),
+        Token::Cw20 { contract } => {
+            let response: cw20::BalanceResponse = deps.querier.query_wasm_smart(
+                contract,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )?;
+            Ok(response.balance)
+        }
+    }
+}
+
+/// Message sending the amount of the token keyed by `denom` to the recipient
+fn transfer_msg(
+    denom: &str,
+    recipient: String,
+    amount: Uint128,
+) -> StdResult<CosmosMsg<NeutronMsg>> {
+    Ok(match Token::from_key(denom) {
+        Token::Native { denom } => CosmosMsg::Bank(BankMsg::Send {
+            to_address: recipient,
+            amount: vec![Coin { denom, amount }],
+        }),
+        Token::Cw20 { contract } => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient, amount })?,
+            funds: vec![],
+        }),
+    })
+}
+
+/// Whether given duration has passed since the top-up
+fn duration_passed(duration: &Duration, top_up: &TopUp, block: &BlockInfo) -> bool {
+    duration_passed_since(duration, top_up.height, top_up.time, block)
+}
+
+fn duration_passed_since(
+    duration: &Duration,
+    height: u64,
+    time: Timestamp,
+    block: &BlockInfo,
+) -> bool {
+    match duration {
+        // saturating, so a huge configured duration never passes instead of overflowing
+        Duration::Height(blocks) => block.height >= height.saturating_add(*blocks),
+        Duration::Time(seconds) => {
+            block.time.nanos()
+                >= time
+                    .nanos()
+                    .saturating_add(seconds.saturating_mul(1_000_000_000))
+        }
+    }
+}
+
+/// Total amount sent to the target within the current spending cap period
+fn spent_within_period(
+    deps: Deps<NeutronQuery>,
+    block: &BlockInfo,
+    address: &Addr,
+    denom: &str,
+    spending_cap: &SpendingCap,
+) -> StdResult<Uint128> {
+    Ok(TOP_UP_HISTORY
+        .may_load(deps.storage, (address, denom))?
+        .unwrap_or_default()
+        .iter()
+        .filter(|top_up| !duration_passed(&spending_cap.period, top_up, block))
+        .map(|top_up| top_up.amount)
+        .sum())
+}
+
+/// Total amount withdrawn by the treasurer within the current withdraw limit period
+fn withdrawn_within_period(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    treasurer: &Addr,
+    denom: &str,
+    cap: &SpendingCap,
+) -> StdResult<Uint128> {
+    Ok(TREASURER_WITHDRAWALS
+        .may_load(storage, (treasurer, denom))?
+        .unwrap_or_default()
+        .iter()
+        .filter(|withdrawal| !duration_passed(&cap.period, withdrawal, block))
+        .map(|withdrawal| withdrawal.amount)
+        .sum())
+}
+
+/// Replaces every target balance, balance queries of previous remote targets are removed and
+/// registered again for the new ones
+fn save_target_balances(
+    deps: DepsMut<NeutronQuery>,
+    target_balances: &[TargetBalance],
+) -> Result<Vec<SubMsg<NeutronMsg>>, ContractError> {
+    for target_balance in target_balances {
+        target_balance.validate(deps.as_ref())?;
+    }
+    let mut messages = REMOTE_BALANCE_QUERIES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (_, query_id) = item?;
+            Ok(SubMsg::new(NeutronMsg::RemoveInterchainQuery { query_id }))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    REMOTE_BALANCE_QUERIES.clear(deps.storage);
+    let previous_targets = TARGET_BALANCES
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    TARGET_BALANCES.clear(deps.storage);
+    REMOTE_TARGETS.clear(deps.storage);
+    for target_balance in target_balances {
+        let address = target_address(deps.as_ref(), target_balance)?;
+        let denom = target_balance.token.key();
+        let key = (&address, denom.as_str());
+        ensure!(
+            !TARGET_BALANCES.has(deps.storage, key),
+            ContractError::DuplicateTargetBalance {
+                address: target_balance.address.clone(),
+                denom: denom.clone(),
+            }
+        );
+        messages.extend(save_target_balance(deps.storage, key, target_balance)?);
+    }
+    for (address, denom) in previous_targets {
+        let key = (&address, denom.as_str());
+        if !TARGET_BALANCES.has(deps.storage, key) {
+            remove_top_ups(deps.storage, key);
+        }
+    }
+    Ok(messages)
+}
+
+/// Forgets top-ups of a removed target, so it doesn't inherit cooldown and spending cap usage
+/// if it's added again
+fn remove_top_ups(storage: &mut dyn Storage, key: (&Addr, &str)) {
+    LAST_TOP_UPS.remove(storage, key);
+    TOP_UP_HISTORY.remove(storage, key);
+}
+
+/// Saves the target balance and returns messages keeping its balance query in sync
+/// with remote chain details
+fn save_target_balance(
+    storage: &mut dyn Storage,
+    key: (&Addr, &str),
+    target_balance: &TargetBalance,
+) -> Result<Vec<SubMsg<NeutronMsg>>, ContractError> {
+    TARGET_BALANCES.save(storage, key, &target_balance.update_options)?;
+    if REMOTE_TARGETS.may_load(storage, key)? == target_balance.remote {
+        return Ok(vec![]);
+    }
+    let mut messages = remove_balance_query(storage, key)?
+        .into_iter()
+        .map(SubMsg::new)
+        .collect::<Vec<_>>();
+    match &target_balance.remote {
+        Some(remote) => {
+            REMOTE_TARGETS.save(storage, key, remote)?;
+            messages.push(register_balance_query(storage, key, remote)?);
+        }
+        None => REMOTE_TARGETS.remove(storage, key),
+    }
+    Ok(messages)
+}
+
+/// Query id is only known from the reply, so the target is queued until it arrives
+fn register_balance_query(
+    storage: &mut dyn Storage,
+    key: (&Addr, &str),
+    remote: &RemoteTarget,
+) -> Result<SubMsg<NeutronMsg>, ContractError> {
+    let config = CONFIG.load(storage)?;
+    let mut pending = PENDING_BALANCE_QUERIES
+        .may_load(storage)?
+        .unwrap_or_default();
+    pending.push((key.0.clone(), key.1.to_string()));
+    PENDING_BALANCE_QUERIES.save(storage, &pending)?;
+    let msg = new_register_balances_query_msg(
+        remote.connection_id.clone(),
+        key.0.to_string(),
+        vec![remote.remote_denom.clone()],
+        config.balance_query_update_period,
+    )?;
+    Ok(SubMsg::reply_on_success(msg, BALANCE_QUERY_REPLY_ID))
+}
+
+fn remove_balance_query(
+    storage: &mut dyn Storage,
+    key: (&Addr, &str),
+) -> StdResult<Option<NeutronMsg>> {
+    let query_id = REMOTE_BALANCE_QUERIES.may_load(storage, key)?;
+    REMOTE_BALANCE_QUERIES.remove(storage, key);
+    Ok(query_id.map(|query_id| NeutronMsg::RemoveInterchainQuery { query_id }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    match msg.id {
+        BALANCE_QUERY_REPLY_ID => reply_register_balance_query(deps, msg),
+        PULL_REPLY_ID => reply_pull_funds(deps, env, msg),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+fn reply_register_balance_query(
+    deps: DepsMut<NeutronQuery>,
+    msg: Reply,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let data = msg
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or_else(|| StdError::generic_err("Register interchain query reply has no data"))?;
+    let query_id = from_json::<MsgRegisterInterchainQueryResponse>(data)?.id;
+    let mut pending = PENDING_BALANCE_QUERIES
+        .may_load(deps.storage)?
+        .unwrap_or_default();
+    ensure!(!pending.is_empty(), ContractError::NoPendingBalanceQuery);
+    let (address, denom) = pending.remove(0);
+    PENDING_BALANCE_QUERIES.save(deps.storage, &pending)?;
+    REMOTE_BALANCE_QUERIES.save(deps.storage, (&address, denom.as_str()), &query_id)?;
+    Ok(response(
+        "reply-register-balance-query",
+        CONTRACT_NAME,
+        [
+            attr("address", address.to_string()),
+            attr("denom", denom),
+            attr("query_id", query_id.to_string()),
+        ],
+    ))
+}
+
+/// Records the amount the pull actually brought against the pull cap
+fn reply_pull_funds(
+    deps: DepsMut<NeutronQuery>,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<NeutronMsg>, ContractError> {
+    let (denom, balance) = PENDING_PULL.load(deps.storage)?;
+    PENDING_PULL.remove(deps.storage);
+    if let SubMsgResult::Err(error) = msg.result {
+        return Ok(response(
+            "reply-pull-funds",
+            CONTRACT_NAME,
+            [attr("denom", denom), attr("error", error)],
+        ));
+    }
+    let received =
+        query_token_balance(deps.as_ref(), &env.contract.address, &denom)?.saturating_sub(balance);
+    let cap = FUNDING_SOURCE.load(deps.storage)?.cap;
+    let mut history = PULL_HISTORY.may_load(deps.storage)?.unwrap_or_default();
+    history.retain(|pull| !duration_passed(&cap.period, pull, &env.block));
+    if !received.is_zero() {
+        history.push(TopUp {
+            height: env.block.height,
+            time: env.block.time,
+            amount: received,
+        });
+    }
+    let pulled: Uint128 = history.iter().map(|pull| pull.amount).sum();
+    PULL_HISTORY.save(deps.storage, &history)?;
+    Ok(response(
+        "reply-pull-funds",
+        CONTRACT_NAME,
+        [
+            attr("denom", denom),
+            attr("received", received),
+            attr("pulled_within_period", pulled),
+        ],
+    ))
+}
+
+/// Remote chain addresses can't be validated on Neutron, so they're stored as is
+fn target_address(deps: Deps<NeutronQuery>, target_balance: &TargetBalance) -> StdResult<Addr> {
+    match target_balance.remote {
+        Some(_) => Ok(Addr::unchecked(&target_balance.address)),
+        None => deps.api.addr_validate(&target_balance.address),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut<NeutronQuery>,
+    _env: Env,
+    _msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let contract_version_metadata = cw2::get_contract_version(deps.storage)?;
+    ensure!(
+        contract_version_metadata.contract == CONTRACT_NAME,
+        ContractError::MigrationContractNameMismatch {
+            storage_contract_name: contract_version_metadata.contract,
+            contract_name: CONTRACT_NAME.to_string(),
+        }
+    );
+    let storage_version: semver::Version = contract_version_metadata.version.parse()?;
+    let version: semver::Version = CONTRACT_VERSION.parse()?;
+    ensure!(
+        storage_version <= version,
+        ContractError::MigrationDowngrade {
+            storage_version: storage_version.to_string(),
+            contract_version: version.to_string(),
+        }
+    );
+    let mut attrs = vec![];
+    if storage_version < version {
+        attrs = migrate_storage(deps.storage, deps.api, &storage_version)?;
+        cw2::set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    }
+    Ok(response("migrate", CONTRACT_NAME, attrs))
 }
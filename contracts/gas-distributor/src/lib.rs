@@ -1,3 +1,4 @@
 pub mod contract;
+pub mod migrations;
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,86 @@
+use cosmwasm_std::{attr, Api, Attribute, Storage};
+use drop_helper_contracts_base::error::gas_distributor::ContractError;
+use semver::Version;
+
+type Migration = fn(&mut dyn Storage, &dyn Api) -> Result<(), ContractError>;
+
+/// Storage migrations in ascending order. Every migration converts the storage layout
+/// of the version it's registered with into the layout of the next released version
+const MIGRATIONS: &[(&str, Migration)] = &[("1.0.0", v1_0_0::migrate)];
+
+pub fn migrate_storage(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    storage_version: &Version,
+) -> Result<Vec<Attribute>, ContractError> {
+    let mut attrs = vec![];
+    for (version, migration) in MIGRATIONS {
+        let version: Version = version.parse()?;
+        if *storage_version <= version {
+            migration(storage, api)?;
+            attrs.push(attr("migrate-storage-from", version.to_string()));
+        }
+    }
+    Ok(attrs)
+}
+
+pub mod v1_0_0 {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{ensure, Api, Storage, Uint128};
+    use cw_storage_plus::Item;
+    use drop_helper_contracts_base::{
+        error::gas_distributor::ContractError,
+        msg::gas_distributor::{Config, TargetBalanceUpdateParams},
+        state::gas_distributor::{CONFIG, TARGET_BALANCES, UNTRN_DENOM},
+    };
+
+    #[cw_serde]
+    pub struct TargetBalanceUpdateParamsV1_0_0 {
+        pub threshold_balance: Uint128,
+        pub target_balance: Uint128,
+    }
+
+    #[cw_serde]
+    pub struct TargetBalanceV1_0_0 {
+        pub address: String,
+        pub update_options: TargetBalanceUpdateParamsV1_0_0,
+    }
+
+    pub const TARGET_BALANCES_V1_0_0: Item<Vec<TargetBalanceV1_0_0>> = Item::new("target_balances");
+
+    /// 1.0.0 only supported `untrn` targets kept in a single vector, so every stored target
+    /// is moved into the keyed map under that denom. Config didn't exist yet, so defaults
+    /// are stored. Invalid and duplicated addresses fail the migration instead of being stored
+    /// or silently overwritten
+    pub fn migrate(storage: &mut dyn Storage, api: &dyn Api) -> Result<(), ContractError> {
+        for target_balance in TARGET_BALANCES_V1_0_0.load(storage)? {
+            let address = api.addr_validate(&target_balance.address).map_err(|_| {
+                ContractError::InvalidTargetAddress {
+                    address: target_balance.address.clone(),
+                }
+            })?;
+            ensure!(
+                !TARGET_BALANCES.has(storage, (&address, UNTRN_DENOM)),
+                ContractError::DuplicateTargetBalance {
+                    address: address.to_string(),
+                    denom: UNTRN_DENOM.to_string(),
+                }
+            );
+            TARGET_BALANCES.save(
+                storage,
+                (&address, UNTRN_DENOM),
+                &TargetBalanceUpdateParams {
+                    threshold_balance: target_balance.update_options.threshold_balance,
+                    target_balance: target_balance.update_options.target_balance,
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+            )?;
+        }
+        TARGET_BALANCES_V1_0_0.remove(storage);
+        CONFIG.save(storage, &Config::default())?;
+        Ok(())
+    }
+}
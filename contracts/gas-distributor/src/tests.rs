@@ -1,23 +1,189 @@
-use crate::contract::{execute, instantiate, query};
+use crate::{
+    contract::{execute, instantiate, migrate, query, reply, sudo},
+    migrations::v1_0_0::{
+        TargetBalanceUpdateParamsV1_0_0, TargetBalanceV1_0_0, TARGET_BALANCES_V1_0_0,
+    },
+};
 use cosmwasm_std::{
     attr, from_json,
     testing::{mock_env, mock_info},
-    Addr, BalanceResponse, BankMsg, Event, Response, SubMsg, Uint128,
+    to_json_binary, Addr, BalanceResponse, BankMsg, Binary, Decimal, DepsMut, Event, Order, Reply,
+    Response, StdResult, Storage, SubMsg, SubMsgResponse, SubMsgResult, Uint128, WasmMsg,
 };
+use cw20::{
+    AllowanceResponse, BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg,
+    Expiration,
+};
+use cw_utils::Duration;
 use drop_helper_contracts_base::{
     error::gas_distributor::ContractError,
     msg::gas_distributor::{
-        ExecuteMsg, InstantiateMsg, QueryMsg, TargetBalance, TargetBalanceUpdateParams,
+        router::{
+            AssetInfo, ExecuteMsg as RouterExecuteMsg, QueryMsg as RouterQueryMsg,
+            SimulateSwapOperationsResponse, SwapOperation,
+        },
+        treasury::ExecuteMsg as TreasuryExecuteMsg,
+        BalanceQuery, Config, ConfigOptional, CronSchedule, Deposit, ExecuteMsg, FundingSource,
+        FundingSourceKind, InstantiateMsg, MigrateMsg, PauseInfo, PendingOperation,
+        PlannedTransfer, QueryMsg, RefillConfig, RemoteTarget, Role, ShortfallPolicy,
+        SimulateDistributeResponse, SkipReason, SkippedTarget, SpendingAllowance, SpendingCap,
+        SponsorAccount, StaleTarget, SudoMsg, TargetBalance, TargetBalanceUpdateParams,
+        TargetStatus, TimelockedOperation, Token, TopUp, WithdrawAllowance, WithdrawLimit,
     },
-    state::gas_distributor::{TARGET_BALANCES, UNTRN_DENOM},
+    state::gas_distributor::{
+        CONFIG, CRON_SCHEDULE, FUNDING_SOURCE, LAST_REFILL, LAST_TOP_UPS, PENDING_PULL,
+        PULL_HISTORY, REFILL_CONFIG, REMOTE_BALANCE_QUERIES, REMOTE_TARGETS, SPONSORED_TOTALS,
+        SPONSOR_BALANCES, TARGET_BALANCES, TOP_UP_HISTORY, UNTRN_DENOM,
+    },
+};
+use drop_helper_contracts_helpers::testing::{mock_dependencies, WasmMockQuerier};
+use neutron_sdk::{
+    bindings::{
+        msg::{IbcFee, MsgExecuteContract, MsgRegisterInterchainQueryResponse, NeutronMsg},
+        query::NeutronQuery,
+    },
+    interchain_queries::v045::register_queries::new_register_balances_query_msg,
+    query::min_ibc_fee::MinIbcFeeResponse,
+    sudo::msg::{RequestPacket, RequestPacketTimeoutHeight},
 };
-use drop_helper_contracts_helpers::testing::mock_dependencies;
+
+fn save_target_balances(storage: &mut dyn Storage, target_balances: Vec<TargetBalance>) {
+    for target_balance in target_balances {
+        TARGET_BALANCES
+            .save(
+                storage,
+                (
+                    &Addr::unchecked(target_balance.address),
+                    target_balance.token.key().as_str(),
+                ),
+                &target_balance.update_options,
+            )
+            .unwrap();
+    }
+}
+
+/// Funds sponsor sub-account the same way `SponsorDeposit` does
+fn save_sponsor_balance(storage: &mut dyn Storage, sponsor: &str, denom: &str, amount: u128) {
+    SPONSOR_BALANCES
+        .save(
+            storage,
+            (&Addr::unchecked(sponsor), denom),
+            &Uint128::from(amount),
+        )
+        .unwrap();
+    SPONSORED_TOTALS
+        .update(storage, denom, |total| -> StdResult<_> {
+            Ok(total.unwrap_or_default() + Uint128::from(amount))
+        })
+        .unwrap();
+}
+
+fn load_target_balances(storage: &dyn Storage) -> Vec<TargetBalance> {
+    TARGET_BALANCES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let ((address, denom), update_options) = item.unwrap();
+            TargetBalance {
+                address: address.to_string(),
+                token: Token::from_key(&denom),
+                update_options,
+                remote: None,
+            }
+        })
+        .collect()
+}
+
+fn distribute_target_event(
+    address: &str,
+    denom: &str,
+    outcome: &str,
+    balance: u128,
+    deficit: u128,
+    amount: u128,
+) -> Event {
+    Event::new("crates.io:drop-helper__drop-gas-distributor-distribute-target").add_attributes(
+        vec![
+            attr("address", address),
+            attr("denom", denom),
+            attr("outcome", outcome),
+            attr("balance", balance.to_string()),
+            attr("deficit", deficit.to_string()),
+            attr("amount", amount.to_string()),
+        ],
+    )
+}
+
+const REMOTE_ADDRESS: &str = "cosmos1qyqszqgpqyqszqgpqyqszqgpqyqszqgpjnp7du";
+
+fn remote_target() -> RemoteTarget {
+    RemoteTarget {
+        connection_id: "connection-0".to_string(),
+        channel: "channel-0".to_string(),
+        remote_denom: "uatom".to_string(),
+    }
+}
+
+/// Mocks balance query `query_id` holding `amount` of `uatom`, submitted at `local_height`
+fn mock_remote_balance(
+    querier: &mut WasmMockQuerier,
+    query_id: u64,
+    amount: u128,
+    local_height: u64,
+) {
+    querier.add_registered_queries(
+        query_id,
+        Binary::from(
+            format!(
+                r#"{{"registered_query":{{"id":{query_id},"owner":"cosmos2contract","query_type":"kv","keys":[],"transactions_filter":"","connection_id":"connection-0","update_period":100,"last_submitted_result_local_height":{local_height},"last_submitted_result_remote_height":{{"revision_number":0,"revision_height":1}},"deposit":[],"submit_timeout":0,"registered_at_height":1}}}}"#
+            )
+            .as_bytes(),
+        ),
+    );
+    let mut key = vec![0x02, 20];
+    key.extend([1_u8; 20]);
+    key.extend(b"uatom");
+    querier.add_query_response(
+        query_id,
+        Binary::from(
+            format!(
+                r#"{{"result":{{"kv_results":[{{"storage_prefix":"bank","key":"{}","value":"{}"}}],"height":1,"revision":0}}}}"#,
+                Binary::from(key).to_base64(),
+                Binary::from(amount.to_string().as_bytes()).to_base64(),
+            )
+            .as_bytes(),
+        ),
+    );
+}
+
+/// Mocks minimal IBC fee of 1000untrn for both ack and timeout
+fn mock_min_ibc_fee(querier: &mut WasmMockQuerier) {
+    querier.add_custom_query_response(|_| {
+        to_json_binary(&MinIbcFeeResponse {
+            min_fee: min_ibc_fee(),
+        })
+        .unwrap()
+    });
+}
+
+fn min_ibc_fee() -> IbcFee {
+    IbcFee {
+        recv_fee: vec![],
+        ack_fee: vec![cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        }],
+        timeout_fee: vec![cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        }],
+    }
+}
 
 #[test]
 fn test_instantiate_general() {
     let mut deps = mock_dependencies(&[]);
     let response = instantiate(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("sender", &[]),
         InstantiateMsg {
@@ -25,19 +191,36 @@ fn test_instantiate_general() {
             initial_target_balances: vec![
                 TargetBalance {
                     address: "address1".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(2000_u64),
                         threshold_balance: Uint128::from(123_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
                 TargetBalance {
                     address: "address2".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(1000_u64),
                         threshold_balance: Uint128::from(321_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
             ],
+            config: None,
         },
     )
     .unwrap();
@@ -56,9 +239,12 @@ fn test_instantiate_general() {
 
     let res: Vec<TargetBalance> = from_json(
         query(
-            deps.as_ref().into_empty(),
+            deps.as_ref(),
             mock_env(),
-            QueryMsg::TargetBalances {},
+            QueryMsg::TargetBalances {
+                start_after: None,
+                limit: None,
+            },
         )
         .unwrap(),
     )
@@ -69,17 +255,33 @@ fn test_instantiate_general() {
         vec![
             TargetBalance {
                 address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string()
+                },
                 update_options: TargetBalanceUpdateParams {
                     target_balance: Uint128::from(2000_u64),
                     threshold_balance: Uint128::from(123_u64),
-                }
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
             },
             TargetBalance {
                 address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string()
+                },
                 update_options: TargetBalanceUpdateParams {
                     target_balance: Uint128::from(1000_u64),
                     threshold_balance: Uint128::from(321_u64),
-                }
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
             }
         ]
     );
@@ -89,7 +291,7 @@ fn test_instantiate_general() {
 fn test_instantiate_custom_owner() {
     let mut deps = mock_dependencies(&[]);
     let response = instantiate(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("sender", &[]),
         InstantiateMsg {
@@ -97,19 +299,36 @@ fn test_instantiate_custom_owner() {
             initial_target_balances: vec![
                 TargetBalance {
                     address: "address1".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(2000_u64),
                         threshold_balance: Uint128::from(123_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
                 TargetBalance {
                     address: "address2".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(1000_u64),
                         threshold_balance: Uint128::from(321_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
             ],
+            config: None,
         },
     )
     .unwrap();
@@ -125,15 +344,8 @@ fn test_instantiate_custom_owner() {
             )
         )
     );
-    let owner: String = from_json(
-        query(
-            deps.as_ref().into_empty(),
-            mock_env(),
-            QueryMsg::Ownership {},
-        )
-        .unwrap(),
-    )
-    .unwrap();
+    let owner: String =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
     assert_eq!(owner, "owner");
 }
 
@@ -141,7 +353,7 @@ fn test_instantiate_custom_owner() {
 fn test_instantiate_sender_owner() {
     let mut deps = mock_dependencies(&[]);
     let response = instantiate(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("sender", &[]),
         InstantiateMsg {
@@ -149,19 +361,36 @@ fn test_instantiate_sender_owner() {
             initial_target_balances: vec![
                 TargetBalance {
                     address: "address1".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(2000_u64),
                         threshold_balance: Uint128::from(123_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
                 TargetBalance {
                     address: "address2".to_string(),
+                    token: Token::Native {
+                        denom: "untrn".to_string(),
+                    },
                     update_options: TargetBalanceUpdateParams {
                         target_balance: Uint128::from(1000_u64),
                         threshold_balance: Uint128::from(321_u64),
+                        priority: 0,
+                        cooldown: None,
+                        spending_cap: None,
+                        sponsor: None,
                     },
+                    remote: None,
                 },
             ],
+            config: None,
         },
     )
     .unwrap();
@@ -177,33 +406,91 @@ fn test_instantiate_sender_owner() {
             )
         )
     );
-    let owner: String = from_json(
-        query(
-            deps.as_ref().into_empty(),
-            mock_env(),
-            QueryMsg::Ownership {},
-        )
-        .unwrap(),
-    )
-    .unwrap();
+    let owner: String =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
     assert_eq!(owner, "sender");
 }
 
+#[test]
+fn test_instantiate_invalid_address() {
+    let mut deps = mock_dependencies(&[]);
+    let error = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        InstantiateMsg {
+            owner: None,
+            initial_target_balances: vec![TargetBalance {
+                address: "xx".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            }],
+            config: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        error,
+        ContractError::InvalidTargetAddress {
+            address: "xx".to_string()
+        }
+    );
+}
+
+#[test]
+fn test_instantiate_empty_denom() {
+    let mut deps = mock_dependencies(&[]);
+    let error = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sender", &[]),
+        InstantiateMsg {
+            owner: None,
+            initial_target_balances: vec![TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            }],
+            config: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        error,
+        ContractError::EmptyTargetDenom {
+            address: "address1".to_string()
+        }
+    );
+}
+
 #[test]
 fn test_query_owner() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
 
-    let owner: String = from_json(
-        query(
-            deps.as_ref().into_empty(),
-            mock_env(),
-            QueryMsg::Ownership {},
-        )
-        .unwrap(),
-    )
-    .unwrap();
+    let owner: String =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
     assert_eq!(owner, "owner");
 }
 
@@ -214,20 +501,27 @@ fn test_query_target_balance() {
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     let expected_params = TargetBalance {
         address: "address".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
         update_options: TargetBalanceUpdateParams {
             target_balance: Uint128::from(2000_u64),
             threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
         },
+        remote: None,
     };
-    TARGET_BALANCES
-        .save(deps_mut.storage, &vec![expected_params.clone()])
-        .unwrap();
+    save_target_balances(deps_mut.storage, vec![expected_params.clone()]);
     let response: TargetBalance = from_json(
         query(
-            deps.as_ref().into_empty(),
+            deps.as_ref(),
             mock_env(),
             QueryMsg::TargetBalance {
                 address: Addr::unchecked("address".to_string()),
+                denom: UNTRN_DENOM.to_string(),
             },
         )
         .unwrap(),
@@ -241,14 +535,12 @@ fn test_query_target_balance_not_exist() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
-    TARGET_BALANCES
-        .save(deps.as_mut().storage, &vec![])
-        .unwrap();
     let response: ContractError = query(
-        deps.as_ref().into_empty(),
+        deps.as_ref(),
         mock_env(),
         QueryMsg::TargetBalance {
             address: Addr::unchecked("address".to_string()),
+            denom: UNTRN_DENOM.to_string(),
         },
     )
     .unwrap_err();
@@ -264,29 +556,46 @@ fn test_query_target_balances() {
     let target_balances = vec![
         TargetBalance {
             address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
             update_options: TargetBalanceUpdateParams {
                 target_balance: Uint128::from(2000_u64),
                 threshold_balance: Uint128::from(123_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
             },
+            remote: None,
         },
         TargetBalance {
             address: "address2".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
             update_options: TargetBalanceUpdateParams {
                 target_balance: Uint128::from(1000_u64),
                 threshold_balance: Uint128::from(321_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
             },
+            remote: None,
         },
     ];
 
-    TARGET_BALANCES
-        .save(deps_mut.storage, &target_balances)
-        .unwrap();
+    save_target_balances(deps_mut.storage, target_balances.clone());
 
     let response: Vec<TargetBalance> = from_json(
         query(
-            deps.as_ref().into_empty(),
+            deps.as_ref(),
             mock_env(),
-            QueryMsg::TargetBalances {},
+            QueryMsg::TargetBalances {
+                start_after: None,
+                limit: None,
+            },
         )
         .unwrap(),
     )
@@ -294,13 +603,94 @@ fn test_query_target_balances() {
     assert_eq!(response, target_balances);
 }
 
+#[test]
+fn test_query_target_balances_paginated() {
+    let mut deps = mock_dependencies(&[]);
+    let target_balances = vec![
+        TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Native {
+                denom: "ibc/uatom".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(2000_u64),
+                threshold_balance: Uint128::from(123_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        },
+        TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(2000_u64),
+                threshold_balance: Uint128::from(123_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        },
+        TargetBalance {
+            address: "address2".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(1000_u64),
+                threshold_balance: Uint128::from(321_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        },
+    ];
+    save_target_balances(deps.as_mut().storage, target_balances.clone());
+
+    let response: Vec<TargetBalance> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetBalances {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(response, target_balances[..2].to_vec());
+
+    let response: Vec<TargetBalance> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetBalances {
+                start_after: Some((Addr::unchecked("address1"), "untrn".to_string())),
+                limit: Some(2),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(response, target_balances[2..].to_vec());
+}
+
 #[test]
 fn test_execute_set_target_balances_unauthorized() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
         mock_info("somebody", &[]),
         ExecuteMsg::SetTargetBalances {
@@ -322,21 +712,53 @@ fn test_execute_set_target_balances() {
     let expected_target_balances = vec![
         TargetBalance {
             address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
             update_options: TargetBalanceUpdateParams {
                 target_balance: Uint128::from(2000_u64),
                 threshold_balance: Uint128::from(123_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
             },
+            remote: None,
         },
         TargetBalance {
             address: "address2".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
             update_options: TargetBalanceUpdateParams {
                 target_balance: Uint128::from(1000_u64),
                 threshold_balance: Uint128::from(321_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
             },
+            remote: None,
         },
     ];
+    let top_up = TopUp {
+        height: 1,
+        time: mock_env().block.time,
+        amount: Uint128::from(100_u128),
+    };
+    for address in ["address1", "address3"] {
+        let key = (&Addr::unchecked(address), "untrn");
+        TARGET_BALANCES
+            .save(
+                deps_mut.storage,
+                key,
+                &expected_target_balances[0].update_options,
+            )
+            .unwrap();
+        LAST_TOP_UPS.save(deps_mut.storage, key, &top_up).unwrap();
+    }
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
         mock_info("owner", &[]),
         ExecuteMsg::SetTargetBalances {
@@ -354,353 +776,5942 @@ fn test_execute_set_target_balances() {
                 ])
         )
     );
-    let target_balances_list = TARGET_BALANCES.load(&deps.storage).unwrap();
-    assert_eq!(target_balances_list, expected_target_balances)
+    let target_balances_list = load_target_balances(&deps.storage);
+    assert_eq!(target_balances_list, expected_target_balances);
+    // top-ups are only forgotten for targets that aren't in the new list
+    assert!(LAST_TOP_UPS.has(&deps.storage, (&Addr::unchecked("address1"), "untrn")));
+    assert!(!LAST_TOP_UPS.has(&deps.storage, (&Addr::unchecked("address3"), "untrn")));
 }
 
 #[test]
-fn test_execute_withdraw_tokens_unauthorized() {
+fn test_execute_set_target_balances_threshold_not_below_target() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
-        mock_info("somebody", &[]),
-        ExecuteMsg::WithdrawTokens {
-            recepient: None,
-            amount: None,
+        mock_info("owner", &[]),
+        ExecuteMsg::SetTargetBalances {
+            target_balances: vec![TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(100_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            }],
         },
     )
     .unwrap_err();
     assert_eq!(
         execute_res,
-        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+        ContractError::ThresholdNotBelowTarget {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        }
     );
 }
 
 #[test]
-fn test_execute_withdraw_tokens_unauthorized_custom_recepient() {
+fn test_execute_set_target_balances_zero_target() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::WithdrawTokens {
-            recepient: Some("recepient".to_string()),
-            amount: None,
+        ExecuteMsg::SetTargetBalances {
+            target_balances: vec![TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::zero(),
+                    threshold_balance: Uint128::zero(),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            }],
         },
     )
-    .unwrap();
+    .unwrap_err();
     assert_eq!(
         execute_res,
-        Response::new()
-            .add_submessage(SubMsg {
-                id: 0,
-                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                    to_address: "recepient".to_string(),
-                    amount: vec![cosmwasm_std::Coin {
-                        denom: "untrn".to_string(),
-                        amount: Uint128::from(0_u128)
-                    }]
-                }),
-                gas_limit: None,
-                reply_on: cosmwasm_std::ReplyOn::Never
-            })
-            .add_event(Event::new(
-                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
-            ))
+        ContractError::ZeroTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        }
     );
 }
 
 #[test]
-fn test_execute_withdraw_tokens_unauthorized_custom_amount_insufficient_funds() {
+fn test_execute_set_target_balances_duplicate() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::WithdrawTokens {
-            recepient: Some("recepient".to_string()),
-            amount: Some(Uint128::from(123_u128)),
+        ExecuteMsg::SetTargetBalances {
+            target_balances: vec![target_balance.clone(), target_balance],
         },
     )
     .unwrap_err();
-    assert_eq!(execute_res, ContractError::InsufficientFunds {});
+    assert_eq!(
+        execute_res,
+        ContractError::DuplicateTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        }
+    );
 }
 
 #[test]
-fn test_execute_withdraw_tokens_unauthorized_custom_amount() {
-    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
-        denom: "untrn".to_string(),
-        amount: Uint128::from(123_u128),
-    }]);
+fn test_execute_add_target_balance() {
+    let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::WithdrawTokens {
-            recepient: None,
-            amount: Some(Uint128::from(123_u128)),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: target_balance.clone(),
         },
     )
     .unwrap();
     assert_eq!(
         execute_res,
-        Response::new()
-            .add_submessage(SubMsg {
-                id: 0,
-                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                    to_address: "owner".to_string(),
-                    amount: vec![cosmwasm_std::Coin {
-                        denom: "untrn".to_string(),
-                        amount: Uint128::from(123_u128)
-                    }]
-                }),
-                gas_limit: None,
-                reply_on: cosmwasm_std::ReplyOn::Never
-            })
-            .add_event(Event::new(
-                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
-            ))
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-add-target-balance")
+                .add_attributes(vec![attr("address", "address1"), attr("denom", "untrn")])
+        )
     );
+    assert_eq!(load_target_balances(&deps.storage), vec![target_balance]);
 }
 
 #[test]
-fn test_execute_withdraw_tokens_unauthorized_custom_amount_and_recepient() {
-    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
-        denom: "untrn".to_string(),
-        amount: Uint128::from(123_u128),
-    }]);
+fn test_execute_add_target_balance_unauthorized() {
+    let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     let execute_res = execute(
-        deps_mut.into_empty(),
+        deps_mut,
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::WithdrawTokens {
-            recepient: Some("recepient".to_string()),
-            amount: Some(Uint128::from(123_u128)),
+        mock_info("somebody", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
         },
     )
-    .unwrap();
+    .unwrap_err();
     assert_eq!(
         execute_res,
-        Response::new()
-            .add_submessage(SubMsg {
-                id: 0,
-                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                    to_address: "recepient".to_string(),
-                    amount: vec![cosmwasm_std::Coin {
-                        denom: "untrn".to_string(),
-                        amount: Uint128::from(123_u128)
-                    }]
-                }),
-                gas_limit: None,
-                reply_on: cosmwasm_std::ReplyOn::Never
-            })
-            .add_event(Event::new(
-                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
-            ))
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
     );
 }
 
 #[test]
-fn test_distribute_2_addresses() {
-    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
-        denom: "untrn".to_string(),
-        amount: Uint128::from(1000_u128),
-    }]);
-    let expected_params = TargetBalanceUpdateParams {
-        target_balance: Uint128::from(100_u64),
-        threshold_balance: Uint128::from(10_u64),
+fn test_execute_add_target_balance_duplicate() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
     };
-    TARGET_BALANCES
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                TargetBalance {
-                    address: "address1".to_string(),
-                    update_options: expected_params.clone(),
+    save_target_balances(deps_mut.storage, vec![target_balance.clone()]);
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance { target_balance },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::DuplicateTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_add_target_balance_zero_spending_cap() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
                 },
-                TargetBalance {
-                    address: "address2".to_string(),
-                    update_options: expected_params,
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: Some(SpendingCap {
+                        amount: Uint128::zero(),
+                        period: Duration::Time(86400),
+                    }),
+                    sponsor: None,
                 },
-            ],
-        )
-        .unwrap();
-    deps.querier.add_bank_query_response(
-        "address2".to_string(),
-        BalanceResponse {
-            amount: cosmwasm_std::Coin {
-                denom: UNTRN_DENOM.to_string(),
-                amount: Uint128::from(9_u128),
+                remote: None,
             },
         },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::ZeroSpendingCap {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        }
     );
+}
+
+#[test]
+fn test_execute_add_remote_target_balance() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    let target_balance = TargetBalance {
+        address: REMOTE_ADDRESS.to_string(),
+        token: Token::Native {
+            denom: "ibc/uatom".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(100_u64),
+            threshold_balance: Uint128::from(10_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: Some(remote_target()),
+    };
     let execute_res = execute(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
-        ExecuteMsg::Distribute {},
+        ExecuteMsg::AddTargetBalance {
+            target_balance: target_balance.clone(),
+        },
     )
     .unwrap();
-
     assert_eq!(
         execute_res,
         Response::new()
-            .add_submessages(vec![
-                SubMsg {
-                    id: 0_u64,
-                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                        to_address: "address1".to_string(),
-                        amount: vec![cosmwasm_std::Coin {
-                            denom: "untrn".to_string(),
-                            amount: Uint128::from(100_u128)
-                        }]
-                    }),
-                    gas_limit: None,
-                    reply_on: cosmwasm_std::ReplyOn::Never
-                },
-                SubMsg {
-                    id: 0_u64,
-                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                        to_address: "address2".to_string(),
-                        amount: vec![cosmwasm_std::Coin {
-                            denom: "untrn".to_string(),
-                            amount: Uint128::from(91_u128)
-                        }]
-                    }),
-                    gas_limit: None,
-                    reply_on: cosmwasm_std::ReplyOn::Never
-                }
-            ])
+            .add_submessage(SubMsg::reply_on_success(
+                new_register_balances_query_msg(
+                    "connection-0".to_string(),
+                    REMOTE_ADDRESS.to_string(),
+                    vec!["uatom".to_string()],
+                    100,
+                )
+                .unwrap(),
+                1,
+            ))
             .add_event(
-                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
-                    .add_attributes(vec![attr("address1", "100"), attr("address2", "91")])
+                Event::new(
+                    "crates.io:drop-helper__drop-gas-distributor-execute-add-target-balance"
+                )
+                .add_attributes(vec![
+                    attr("address", REMOTE_ADDRESS),
+                    attr("denom", "ibc/uatom"),
+                ])
             )
     );
+    let reply_res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: Some(to_json_binary(&MsgRegisterInterchainQueryResponse { id: 7 }).unwrap()),
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        reply_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-reply-register-balance-query")
+                .add_attributes(vec![
+                    attr("address", REMOTE_ADDRESS),
+                    attr("denom", "ibc/uatom"),
+                    attr("query_id", "7"),
+                ])
+        )
+    );
+    let query_res: TargetBalance = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetBalance {
+                address: Addr::unchecked(REMOTE_ADDRESS),
+                denom: "ibc/uatom".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(query_res, target_balance);
+    let query_res: Vec<BalanceQuery> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::BalanceQueries {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![BalanceQuery {
+            address: REMOTE_ADDRESS.to_string(),
+            denom: "ibc/uatom".to_string(),
+            query_id: 7,
+        }]
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveTargetBalance {
+            address: REMOTE_ADDRESS.to_string(),
+            denom: "ibc/uatom".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(NeutronMsg::RemoveInterchainQuery {
+            query_id: 7
+        })]
+    );
+    let key = (&Addr::unchecked(REMOTE_ADDRESS), "ibc/uatom");
+    assert!(!REMOTE_TARGETS.has(deps.as_ref().storage, key));
+    assert!(!REMOTE_BALANCE_QUERIES.has(deps.as_ref().storage, key));
 }
 
 #[test]
-fn test_distribute_1_address() {
-    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
-        denom: "untrn".to_string(),
-        amount: Uint128::from(150_u128),
-    }]);
-    let expected_params = TargetBalanceUpdateParams {
-        target_balance: Uint128::from(100_u64),
-        threshold_balance: Uint128::from(10_u64),
-    };
-    TARGET_BALANCES
-        .save(
-            deps.as_mut().storage,
-            &vec![
-                TargetBalance {
-                    address: "address1".to_string(),
-                    update_options: expected_params.clone(),
+fn test_reply_unknown_id() {
+    let mut deps = mock_dependencies(&[]);
+    let reply_res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 42,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(reply_res, ContractError::UnknownReplyId { id: 42 });
+}
+
+#[test]
+fn test_execute_add_remote_target_balance_invalid() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "cosmos1remote".to_string(),
+                token: Token::Native {
+                    denom: "ibc/uatom".to_string(),
                 },
-                TargetBalance {
-                    address: "address2".to_string(),
-                    update_options: expected_params,
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
                 },
-            ],
+                remote: Some(RemoteTarget {
+                    channel: "".to_string(),
+                    ..remote_target()
+                }),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::InvalidRemoteTarget {
+            address: "cosmos1remote".to_string(),
+            denom: "ibc/uatom".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_update_target_balance() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    save_target_balances(
+        deps_mut.storage,
+        vec![TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(2000_u64),
+                threshold_balance: Uint128::from(123_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        }],
+    );
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(5000_u64),
+            threshold_balance: Uint128::from(1000_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateTargetBalance {
+            target_balance: target_balance.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-update-target-balance")
+                .add_attributes(vec![attr("address", "address1"), attr("denom", "untrn")])
         )
-        .unwrap();
-    deps.querier.add_bank_query_response(
-        "address1".to_string(),
-        BalanceResponse {
-            amount: cosmwasm_std::Coin {
-                denom: UNTRN_DENOM.to_string(),
-                amount: Uint128::from(9_u128),
+    );
+    assert_eq!(load_target_balances(&deps.storage), vec![target_balance]);
+}
+
+#[test]
+fn test_execute_update_target_balance_unknown() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateTargetBalance {
+            target_balance: TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::UnknownTargetBalance);
+}
+
+#[test]
+fn test_execute_remove_target_balance() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let remaining_target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "ibc/uatom".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
+    save_target_balances(
+        deps_mut.storage,
+        vec![
+            remaining_target_balance.clone(),
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    let top_up = TopUp {
+        height: 1,
+        time: mock_env().block.time,
+        amount: Uint128::from(100_u128),
+    };
+    for denom in ["ibc/uatom", "untrn"] {
+        let key = (&Addr::unchecked("address1"), denom);
+        LAST_TOP_UPS.save(deps_mut.storage, key, &top_up).unwrap();
+        TOP_UP_HISTORY
+            .save(deps_mut.storage, key, &vec![top_up.clone()])
+            .unwrap();
+    }
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-remove-target-balance")
+                .add_attributes(vec![attr("address", "address1"), attr("denom", "untrn")])
+        )
+    );
+    assert_eq!(
+        load_target_balances(&deps.storage),
+        vec![remaining_target_balance]
+    );
+    // removed target doesn't keep its top-ups, so it starts afresh if added again
+    let removed_key = (&Addr::unchecked("address1"), "untrn");
+    assert!(!LAST_TOP_UPS.has(&deps.storage, removed_key));
+    assert!(!TOP_UP_HISTORY.has(&deps.storage, removed_key));
+    let remaining_key = (&Addr::unchecked("address1"), "ibc/uatom");
+    assert_eq!(
+        LAST_TOP_UPS.load(&deps.storage, remaining_key).unwrap(),
+        top_up
+    );
+    assert!(TOP_UP_HISTORY.has(&deps.storage, remaining_key));
+}
+
+#[test]
+fn test_execute_remove_target_balance_unknown() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::UnknownTargetBalance);
+}
+
+#[test]
+fn test_execute_withdraw_tokens_unauthorized() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: None,
+            denom: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_unauthorized_custom_recepient() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: Some("recepient".to_string()),
+            amount: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessage(SubMsg {
+                id: 0,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recepient".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(0_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            })
+            .add_event(Event::new(
+                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
+            ))
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_unauthorized_custom_amount_insufficient_funds() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: Some("recepient".to_string()),
+            amount: Some(Uint128::from(123_u128)),
+            denom: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::InsufficientFunds {});
+}
+
+#[test]
+fn test_execute_withdraw_tokens_unauthorized_custom_amount() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(123_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: Some(Uint128::from(123_u128)),
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessage(SubMsg {
+                id: 0,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "owner".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(123_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            })
+            .add_event(Event::new(
+                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
+            ))
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_unauthorized_custom_amount_and_recepient() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(123_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: Some("recepient".to_string()),
+            amount: Some(Uint128::from(123_u128)),
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessage(SubMsg {
+                id: 0,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "recepient".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(123_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            })
+            .add_event(Event::new(
+                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
+            ))
+    );
+}
+
+#[test]
+fn test_distribute_2_addresses() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let expected_params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params,
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address2".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address1".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(100_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address2".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(91_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+            ])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "2"),
+                        attr("total_sent", "191untrn"),
+                        attr("remaining_balance", "809untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "funded", 0, 100, 100),
+                distribute_target_event("address2", "untrn", "funded", 9, 91, 91),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_1_address() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let expected_params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params,
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address1".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![SubMsg {
+                id: 0_u64,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "address1".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(91_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            },])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "91untrn"),
+                        attr("remaining_balance", "59untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "funded", 9, 91, 91),
+                distribute_target_event("address2", "untrn", "insufficient_funds", 0, 100, 0),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_nobody() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(10_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let expected_params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params,
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address2".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(0_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "0"),
+                        attr("total_sent", "0untrn"),
+                        attr("remaining_balance", "10untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "insufficient_funds", 0, 100, 0),
+                distribute_target_event("address2", "untrn", "insufficient_funds", 0, 100, 0),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_multiple_denoms() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(150_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(50_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let expected_params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "ibc/uatom".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(40_u64),
+                    threshold_balance: Uint128::from(20_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "ibc/uatom".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(40_u64),
+                    threshold_balance: Uint128::from(20_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address1".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(100_u128),
+            },
+        },
+    );
+    deps.querier.add_bank_query_response(
+        "address1".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(5_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![SubMsg {
+                id: 0_u64,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "address1".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "ibc/uatom".to_string(),
+                        amount: Uint128::from(35_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            },])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "35ibc/uatom"),
+                        attr("remaining_balance", "15ibc/uatom"),
+                        attr("total_sent", "0untrn"),
+                        attr("remaining_balance", "150untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "ibc/uatom", "funded", 5, 35, 35),
+                distribute_target_event("address2", "ibc/uatom", "insufficient_funds", 0, 40, 0),
+                distribute_target_event("address1", "untrn", "healthy", 100, 0, 0),
+            ])
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_recipient_allowlist() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                withdraw_recipients: vec![Addr::unchecked("treasury")],
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let withdraw = |recepient: &str| ExecuteMsg::WithdrawTokens {
+        recepient: Some(recepient.to_string()),
+        amount: Some(Uint128::from(100_u128)),
+        denom: None,
+    };
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        withdraw("INVALID"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::InvalidWithdrawRecipient {
+            recipient: "INVALID".to_string(),
+        }
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        withdraw("somebody"),
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::WithdrawRecipientNotAllowed {
+            recipient: "somebody".to_string(),
+        }
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        withdraw("treasury"),
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "treasury".to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(100_u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_cap() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    let withdraw_limit = |amount: u128| WithdrawLimit {
+        denom: "untrn".to_string(),
+        cap: SpendingCap {
+            amount: Uint128::from(amount),
+            period: Duration::Height(10),
+        },
+    };
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                withdraw_limits: Some(vec![withdraw_limit(0)]),
+                ..ConfigOptional::default()
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::ZeroWithdrawCap {
+            denom: "untrn".to_string(),
+        }
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                withdraw_limits: Some(vec![withdraw_limit(100)]),
+                ..ConfigOptional::default()
+            },
+        },
+    )
+    .unwrap();
+    let withdraw = |amount: u128| ExecuteMsg::WithdrawTokens {
+        recepient: None,
+        amount: Some(Uint128::from(amount)),
+        denom: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        withdraw(70),
+    )
+    .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        withdraw(40),
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::WithdrawCapExceeded {
+            denom: "untrn".to_string(),
+        }
+    );
+    let mut env = mock_env();
+    env.block.height += 10;
+    execute(deps.as_mut(), env, mock_info("owner", &[]), withdraw(40)).unwrap();
+}
+
+#[test]
+fn test_execute_update_config_loosen_withdraw_restrictions() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let withdraw_limit = |amount: u128, period: Duration| WithdrawLimit {
+        denom: "untrn".to_string(),
+        cap: SpendingCap {
+            amount: Uint128::from(amount),
+            period,
+        },
+    };
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                withdraw_recipients: vec![
+                    Addr::unchecked("treasury"),
+                    Addr::unchecked("treasury2"),
+                ],
+                withdraw_limits: vec![withdraw_limit(100, Duration::Height(10))],
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let update_config = |withdraw_recipients: Option<Vec<&str>>,
+                         withdraw_limits: Option<Vec<WithdrawLimit>>| {
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                withdraw_recipients: withdraw_recipients.map(|recipients| {
+                    recipients
+                        .into_iter()
+                        .map(|recipient| recipient.to_string())
+                        .collect()
+                }),
+                withdraw_limits,
+                ..ConfigOptional::default()
+            },
+        }
+    };
+    let loosening = vec![
+        update_config(Some(vec![]), None),
+        update_config(Some(vec!["treasury", "somebody"]), None),
+        update_config(None, Some(vec![])),
+        update_config(None, Some(vec![withdraw_limit(101, Duration::Height(10))])),
+        update_config(None, Some(vec![withdraw_limit(100, Duration::Height(9))])),
+        update_config(None, Some(vec![withdraw_limit(100, Duration::Time(60))])),
+    ];
+    for msg in loosening {
+        let execute_res =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(execute_res, ContractError::WithdrawRestrictionsLoosened);
+    }
+    // tightening doesn't need timelock
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config(
+            Some(vec!["treasury"]),
+            Some(vec![withdraw_limit(50, Duration::Height(20))]),
+        ),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                timelock: Some(Duration::Height(10)),
+                ..ConfigOptional::default()
+            },
+        },
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeOperation {
+            operation: TimelockedOperation::UpdateConfig {
+                new_config: ConfigOptional {
+                    withdraw_recipients: Some(vec![]),
+                    withdraw_limits: Some(vec![]),
+                    ..ConfigOptional::default()
+                },
+            },
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height += 10;
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 0 },
+    )
+    .unwrap();
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert!(config.withdraw_recipients.is_empty());
+    assert!(config.withdraw_limits.is_empty());
+}
+
+#[test]
+fn test_execute_withdraw_balances_sweep() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "factory/neutron1airdrop/drop".to_string(),
+            amount: Uint128::from(7_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(50_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+    ]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    save_sponsor_balance(deps_mut.storage, "sponsor1", "untrn", 100);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::WithdrawBalances {
+            recepient: None,
+            coins: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawBalances {
+            recepient: None,
+            coins: None,
+        },
+    )
+    .unwrap();
+    let send = |denom: &str, amount: u128| {
+        SubMsg::<NeutronMsg>::new(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: denom.to_string(),
+                amount: Uint128::from(amount),
+            }],
+        })
+    };
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![
+                send("factory/neutron1airdrop/drop", 7),
+                send("ibc/uatom", 50),
+                // sponsor sub-account isn't swept
+                send("untrn", 900),
+            ])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-withdraw-balances")
+                    .add_attributes(vec![
+                        attr("recipient", "owner"),
+                        attr(
+                            "amount",
+                            "7factory/neutron1airdrop/drop,50ibc/uatom,900untrn"
+                        ),
+                    ])
+            )
+    );
+}
+
+#[test]
+fn test_execute_withdraw_balances_coins() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(50_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+    ]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let coins = |uatom: u128| {
+        vec![
+            cosmwasm_std::Coin {
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(uatom),
+            },
+            cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(10_u128),
+            },
+        ]
+    };
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawBalances {
+            recepient: Some("recepient".to_string()),
+            coins: Some(coins(51)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::InsufficientFunds);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawBalances {
+            recepient: Some("recepient".to_string()),
+            coins: Some(coins(20)),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        coins(20)
+            .into_iter()
+            .map(|coin| SubMsg::<NeutronMsg>::new(BankMsg::Send {
+                to_address: "recepient".to_string(),
+                amount: vec![coin],
+            }))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_execute_withdraw_balances_invalid_coins() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    save_sponsor_balance(deps_mut.storage, "sponsor1", "untrn", 600);
+    let untrn = |amount: u128| cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(amount),
+    };
+    for (coins, expected_error) in [
+        // each coin fits into shared balance of 400 alone, but not together
+        (
+            vec![untrn(300), untrn(300)],
+            ContractError::DuplicateWithdrawDenom {
+                denom: "untrn".to_string(),
+            },
+        ),
+        (
+            vec![untrn(0)],
+            ContractError::ZeroWithdrawAmount {
+                denom: "untrn".to_string(),
+            },
+        ),
+    ] {
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::WithdrawBalances {
+                recepient: None,
+                coins: Some(coins),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(execute_res, expected_error);
+    }
+}
+
+#[test]
+fn test_execute_withdraw_tokens_custom_denom() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "ibc/uatom".to_string(),
+        amount: Uint128::from(123_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps_mut,
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: None,
+            denom: Some("ibc/uatom".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessage(SubMsg {
+                id: 0,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "owner".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "ibc/uatom".to_string(),
+                        amount: Uint128::from(123_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            })
+            .add_event(Event::new(
+                "crates.io:drop-helper__drop-gas-distributor-execute-withdraw-tokens"
+            ))
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_cw20() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    deps.querier.add_wasm_query_response("token", |_| {
+        to_json_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(123_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: None,
+            denom: Some("cw20:token".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "token".to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "owner".to_string(),
+                amount: Uint128::from(123_u128),
+            })
+            .unwrap(),
+            funds: vec![],
+        })]
+    );
+}
+
+#[test]
+fn test_distribute_cw20() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Cw20 {
+                contract: Addr::unchecked("token"),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(10_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        }],
+    );
+    // target balance is queried first, then contract balance
+    deps.querier.add_wasm_query_response("token", |msg| {
+        assert_eq!(
+            from_json::<Cw20QueryMsg>(msg).unwrap(),
+            Cw20QueryMsg::Balance {
+                address: "address1".to_string(),
+            }
+        );
+        to_json_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(5_u128),
+        })
+        .unwrap()
+    });
+    deps.querier.add_wasm_query_response("token", |msg| {
+        assert_eq!(
+            from_json::<Cw20QueryMsg>(msg).unwrap(),
+            Cw20QueryMsg::Balance {
+                address: "cosmos2contract".to_string(),
+            }
+        );
+        to_json_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(1000_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "address1".to_string(),
+                    amount: Uint128::from(95_u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "95cw20:token"),
+                        attr("remaining_balance", "905cw20:token")
+                    ])
+            )
+            .add_event(distribute_target_event(
+                "address1",
+                "cw20:token",
+                "funded",
+                5,
+                95,
+                95
+            ))
+    );
+}
+
+#[test]
+fn test_distribute_cw20_balance_unavailable() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let update_options = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Cw20 {
+                    contract: Addr::unchecked("token"),
+                },
+                update_options: update_options.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options,
+                remote: None,
+            },
+        ],
+    );
+    // "token" isn't a cw20 contract, so neither target nor contract balance can be read
+    for _ in 0..2 {
+        deps.querier
+            .add_wasm_query_response("token", |_| to_json_binary(&"unknown query").unwrap());
+    }
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: "address2".to_string(),
+                amount: vec![cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(100_u128),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "0cw20:token"),
+                        attr("remaining_balance", "0cw20:token"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "900untrn"),
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "cw20:token", "balance_unavailable", 0, 100, 0),
+                distribute_target_event("address2", "untrn", "funded", 0, 100, 100),
+            ])
+    );
+}
+
+#[test]
+fn test_execute_add_target_balance_cw20_remote() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "cosmos1remote".to_string(),
+                token: Token::Cw20 {
+                    contract: Addr::unchecked("token"),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: Some(remote_target()),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::InvalidRemoteTarget {
+            address: "cosmos1remote".to_string(),
+            denom: "cw20:token".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_add_target_balance_cw20_sponsor() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Cw20 {
+                    contract: Addr::unchecked("token"),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: Some(Addr::unchecked("sponsor1")),
+                },
+                remote: None,
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::SponsorNotAllowed {
+            address: "address1".to_string(),
+            denom: "cw20:token".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_add_target_balance_remote_sponsor() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: TargetBalance {
+                address: "cosmos1remote".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: Some(Addr::unchecked("sponsor1")),
+                },
+                remote: Some(remote_target()),
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::SponsorNotAllowed {
+            address: "cosmos1remote".to_string(),
+            denom: "untrn".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_distribute_priority_skip() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(100_u128),
+    }]);
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                shortfall_policy: ShortfallPolicy::Skip,
+                distribute_allowlist: vec![],
+                ibc_transfer_timeout: 600,
+                balance_query_update_period: 100,
+                max_balance_age: 1000,
+                accepted_denoms: vec![],
+                timelock: None,
+                withdraw_recipients: vec![],
+                withdraw_limits: vec![],
+            },
+        )
+        .unwrap();
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 1,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address2".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![SubMsg {
+                id: 0_u64,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "address2".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(91_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            },])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "91untrn"),
+                        attr("remaining_balance", "9untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address2", "untrn", "funded", 9, 91, 91),
+                distribute_target_event("address1", "untrn", "insufficient_funds", 0, 100, 0),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_priority_partial_top_up() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(100_u128),
+    }]);
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                shortfall_policy: ShortfallPolicy::PartialTopUp,
+                distribute_allowlist: vec![],
+                ibc_transfer_timeout: 600,
+                balance_query_update_period: 100,
+                max_balance_age: 1000,
+                accepted_denoms: vec![],
+                timelock: None,
+                withdraw_recipients: vec![],
+                withdraw_limits: vec![],
+            },
+        )
+        .unwrap();
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 1,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address2".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address2".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(91_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address1".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(9_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+            ])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "2"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "0untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address2", "untrn", "funded", 9, 91, 91),
+                distribute_target_event("address1", "untrn", "partially_funded", 0, 100, 9),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_pro_rata() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(100_u128),
+    }]);
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                shortfall_policy: ShortfallPolicy::ProRata,
+                distribute_allowlist: vec![],
+                ibc_transfer_timeout: 600,
+                balance_query_update_period: 100,
+                max_balance_age: 1000,
+                accepted_denoms: vec![],
+                timelock: None,
+                withdraw_recipients: vec![],
+                withdraw_limits: vec![],
+            },
+        )
+        .unwrap();
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address3".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(40_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 1,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address3".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(40_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address1".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(30_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+                SubMsg {
+                    id: 0_u64,
+                    msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                        to_address: "address2".to_string(),
+                        amount: vec![cosmwasm_std::Coin {
+                            denom: "untrn".to_string(),
+                            amount: Uint128::from(30_u128)
+                        }]
+                    }),
+                    gas_limit: None,
+                    reply_on: cosmwasm_std::ReplyOn::Never
+                },
+            ])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "3"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "0untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address3", "untrn", "funded", 0, 40, 40),
+                distribute_target_event("address1", "untrn", "partially_funded", 0, 100, 30),
+                distribute_target_event("address2", "untrn", "partially_funded", 0, 100, 30),
+            ])
+    );
+}
+
+#[test]
+fn test_execute_deposit() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let funds = [
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(100_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(50_u128),
+        },
+    ];
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("team1", &funds),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-deposit")
+                .add_attributes(vec![
+                    attr("depositor", "team1"),
+                    attr("amount", "100untrn,50ibc/uatom"),
+                ])
+        )
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("team1", &funds[..1]),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("team2", &funds[..1]),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap();
+    let query_res: Vec<Deposit> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Deposits {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![
+            Deposit {
+                depositor: "team1".to_string(),
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(50_u128),
+            },
+            Deposit {
+                depositor: "team1".to_string(),
+                denom: "untrn".to_string(),
+                amount: Uint128::from(200_u128),
+            },
+            Deposit {
+                depositor: "team2".to_string(),
+                denom: "untrn".to_string(),
+                amount: Uint128::from(100_u128),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_execute_deposit_not_accepted() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                accepted_denoms: vec!["untrn".to_string()],
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "team1",
+            &[cosmwasm_std::Coin {
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(50_u128),
+            }],
+        ),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::DenomNotAccepted {
+            denom: "ibc/uatom".to_string(),
+        }
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("team1", &[]),
+        ExecuteMsg::Deposit {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::NoFunds);
+}
+
+#[test]
+fn test_execute_sponsor_deposit_and_withdraw() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(
+            "sponsor1",
+            &[cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(100_u128),
+            }],
+        ),
+        ExecuteMsg::SponsorDeposit {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-sponsor-deposit")
+                .add_attributes(vec![
+                    attr("sponsor", "sponsor1"),
+                    attr("amount", "100untrn")
+                ])
+        )
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sponsor1", &[]),
+        ExecuteMsg::SponsorWithdraw {
+            denom: "untrn".to_string(),
+            amount: Some(Uint128::from(40_u128)),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: "sponsor1".to_string(),
+                amount: vec![cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(40_u128),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-sponsor-withdraw")
+                    .add_attributes(vec![
+                        attr("sponsor", "sponsor1"),
+                        attr("denom", "untrn"),
+                        attr("amount", "40"),
+                    ])
+            )
+    );
+    let query_res: Vec<SponsorAccount> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SponsorAccounts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![SponsorAccount {
+            sponsor: "sponsor1".to_string(),
+            denom: "untrn".to_string(),
+            balance: Uint128::from(60_u128),
+            spent: Uint128::zero(),
+        }]
+    );
+    assert_eq!(
+        SPONSORED_TOTALS
+            .load(deps.as_ref().storage, "untrn")
+            .unwrap(),
+        Uint128::from(60_u128)
+    );
+}
+
+#[test]
+fn test_execute_sponsor_withdraw_insufficient_funds() {
+    let mut deps = mock_dependencies(&[]);
+    save_sponsor_balance(deps.as_mut().storage, "sponsor1", "untrn", 100);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sponsor1", &[]),
+        ExecuteMsg::SponsorWithdraw {
+            denom: "untrn".to_string(),
+            amount: Some(Uint128::from(101_u128)),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::InsufficientFunds);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("sponsor2", &[]),
+        ExecuteMsg::SponsorWithdraw {
+            denom: "untrn".to_string(),
+            amount: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::InsufficientFunds);
+}
+
+#[test]
+fn test_execute_withdraw_tokens_sponsored_balance() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    save_sponsor_balance(deps.as_mut().storage, "sponsor1", "untrn", 100);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: Some(Uint128::from(51_u128)),
+            denom: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::InsufficientFunds);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: None,
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(50_u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn test_distribute_sponsored_target() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    save_sponsor_balance(deps.as_mut().storage, "sponsor1", "untrn", 100);
+    let update_options = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    sponsor: Some(Addr::unchecked("sponsor1")),
+                    ..update_options.clone()
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options,
+                remote: None,
+            },
+        ],
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: "address1".to_string(),
+                amount: vec![cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(100_u128),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "50untrn"),
+                    ])
+            )
+            .add_events(vec![
+                // shared balance left after setting sponsored funds aside doesn't cover address2
+                distribute_target_event("address2", "untrn", "insufficient_funds", 0, 100, 0),
+                distribute_target_event("address1", "untrn", "funded", 0, 100, 100),
+            ])
+    );
+    let query_res: Vec<SponsorAccount> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SponsorAccounts {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![SponsorAccount {
+            sponsor: "sponsor1".to_string(),
+            denom: "untrn".to_string(),
+            balance: Uint128::zero(),
+            spent: Uint128::from(100_u128),
+        }]
+    );
+    assert_eq!(
+        SPONSORED_TOTALS
+            .load(deps.as_ref().storage, "untrn")
+            .unwrap(),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_distribute_sponsored_targets_drained_balance() {
+    // sub-accounts add up to more than the contract holds
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let mut target = |address: &str, sponsor: &str| {
+        save_sponsor_balance(deps.as_mut().storage, sponsor, "untrn", 100);
+        TargetBalance {
+            address: address.to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(10_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: Some(Addr::unchecked(sponsor)),
+            },
+            remote: None,
+        }
+    };
+    let target_balances = vec![
+        target("address1", "sponsor1"),
+        target("address2", "sponsor2"),
+    ];
+    save_target_balances(deps.as_mut().storage, target_balances);
+    let response: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SimulateDistribute {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        response,
+        SimulateDistributeResponse {
+            transfers: vec![PlannedTransfer {
+                address: "address1".to_string(),
+                amount: cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(100_u128),
+                },
+                deficit: Uint128::from(100_u128),
+            }],
+            skipped: vec![SkippedTarget {
+                address: "address2".to_string(),
+                denom: "untrn".to_string(),
+                reason: SkipReason::InsufficientFunds,
+            }],
+            remaining_balances: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(50_u128),
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_distribute_not_allowed() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                shortfall_policy: ShortfallPolicy::Skip,
+                distribute_allowlist: vec![Addr::unchecked("keeper")],
+                ibc_transfer_timeout: 600,
+                balance_query_update_period: 100,
+                max_balance_age: 1000,
+                accepted_denoms: vec![],
+                timelock: None,
+                withdraw_recipients: vec![],
+                withdraw_limits: vec![],
+            },
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::DistributeNotAllowed);
+}
+
+#[test]
+fn test_distribute_allowed() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                shortfall_policy: ShortfallPolicy::Skip,
+                distribute_allowlist: vec![Addr::unchecked("keeper")],
+                ibc_transfer_timeout: 600,
+                balance_query_update_period: 100,
+                max_balance_age: 1000,
+                accepted_denoms: vec![],
+                timelock: None,
+                withdraw_recipients: vec![],
+                withdraw_limits: vec![],
+            },
+        )
+        .unwrap();
+    for sender in ["keeper", "owner"] {
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .unwrap();
+        assert_eq!(
+            execute_res,
+            Response::new().add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![attr("funded_targets", "0")])
+            )
+        );
+    }
+}
+
+#[test]
+fn test_distribute_distributor_role() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Distributor,
+            address: "keeper".to_string(),
+            withdraw_limits: vec![],
+        },
+    )
+    .unwrap();
+    // granting the role alone doesn't restrict distribution
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                distribute_allowlist: vec![Addr::unchecked("cron")],
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::DistributeNotAllowed);
+    for sender in ["cron", "keeper", "owner"] {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(sender, &[]),
+            ExecuteMsg::Distribute {},
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_execute_grant_and_revoke_role() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::TargetManager,
+            address: "manager".to_string(),
+            withdraw_limits: vec![],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-grant-role")
+                .add_attributes(vec![
+                    attr("role", "target_manager"),
+                    attr("address", "manager")
+                ])
+        )
+    );
+    let query_res: Vec<String> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::RoleHolders {
+                role: Role::TargetManager,
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(query_res, vec!["manager".to_string()]);
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: target_balance.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(load_target_balances(&deps.storage), vec![target_balance]);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RevokeRole {
+            role: Role::TargetManager,
+            address: "manager".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-revoke-role")
+                .add_attributes(vec![
+                    attr("role", "target_manager"),
+                    attr("address", "manager")
+                ])
+        )
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        ExecuteMsg::RemoveTargetBalance {
+            address: "address1".to_string(),
+            denom: "untrn".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RevokeRole {
+            role: Role::TargetManager,
+            address: "manager".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::RoleNotGranted {
+            role: "target_manager".to_string(),
+            address: "manager".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_grant_role_invalid() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let withdraw_limits = vec![WithdrawLimit {
+        denom: "untrn".to_string(),
+        cap: SpendingCap {
+            amount: Uint128::zero(),
+            period: Duration::Height(100),
+        },
+    }];
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("manager", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::TargetManager,
+            address: "manager".to_string(),
+            withdraw_limits: vec![],
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: "pauser".to_string(),
+            withdraw_limits: withdraw_limits.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::WithdrawLimitsNotAllowed);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Treasurer,
+            address: "treasurer".to_string(),
+            withdraw_limits,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::ZeroSpendingCap {
+            address: "treasurer".to_string(),
+            denom: "untrn".to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_execute_withdraw_tokens_treasurer() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+    ]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let cap = SpendingCap {
+        amount: Uint128::from(100_u128),
+        period: Duration::Height(100),
+    };
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Treasurer,
+            address: "treasurer".to_string(),
+            withdraw_limits: vec![WithdrawLimit {
+                denom: "untrn".to_string(),
+                cap: cap.clone(),
+            }],
+        },
+    )
+    .unwrap();
+    let withdraw = |amount: u128, denom: &str| ExecuteMsg::WithdrawTokens {
+        recepient: None,
+        amount: Some(Uint128::from(amount)),
+        denom: Some(denom.to_string()),
+    };
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("treasurer", &[]),
+        withdraw(60, "untrn"),
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "treasurer".to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(60_u128),
+            }],
+        })]
+    );
+    for denom in ["untrn", "ibc/uatom"] {
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("treasurer", &[]),
+            withdraw(50, denom),
+        )
+        .unwrap_err();
+        assert_eq!(
+            execute_res,
+            ContractError::WithdrawLimitExceeded {
+                denom: denom.to_string(),
+            }
+        );
+    }
+    let query_res: Vec<WithdrawAllowance> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::WithdrawAllowances {
+                address: Addr::unchecked("treasurer"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![WithdrawAllowance {
+            denom: "untrn".to_string(),
+            cap,
+            withdrawn: Uint128::from(60_u128),
+            remaining: Uint128::from(40_u128),
+        }]
+    );
+    let mut env = mock_env();
+    env.block.height += 100;
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("treasurer", &[]),
+        withdraw(100, "untrn"),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_distribute_cooldown() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let update_options = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: Some(Duration::Height(10)),
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: update_options.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options,
+                remote: None,
+            },
+        ],
+    );
+    let env = mock_env();
+    LAST_TOP_UPS
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address1"), "untrn"),
+            &TopUp {
+                height: env.block.height - 5,
+                time: env.block.time.minus_seconds(30),
+                amount: Uint128::from(100_u128),
+            },
+        )
+        .unwrap();
+    LAST_TOP_UPS
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address2"), "untrn"),
+            &TopUp {
+                height: env.block.height - 10,
+                time: env.block.time.minus_seconds(60),
+                amount: Uint128::from(100_u128),
+            },
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![SubMsg {
+                id: 0_u64,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "address2".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(100_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            }])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "900untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "cooldown", 0, 100, 0),
+                distribute_target_event("address2", "untrn", "funded", 0, 100, 100),
+            ])
+    );
+    let last_top_up: Option<TopUp> = from_json(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::LastTopUp {
+                address: Addr::unchecked("address2"),
+                denom: "untrn".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        last_top_up,
+        Some(TopUp {
+            height: env.block.height,
+            time: env.block.time,
+            amount: Uint128::from(100_u128),
+        })
+    );
+}
+
+#[test]
+fn test_distribute_cooldown_overflow() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let target = |address: &str, cooldown: Duration| TargetBalance {
+        address: address.to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(100_u64),
+            threshold_balance: Uint128::from(10_u64),
+            priority: 0,
+            cooldown: Some(cooldown),
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            target("address1", Duration::Height(u64::MAX)),
+            target("address2", Duration::Time(u64::MAX)),
+        ],
+    );
+    let env = mock_env();
+    for address in ["address1", "address2"] {
+        LAST_TOP_UPS
+            .save(
+                deps.as_mut().storage,
+                (&Addr::unchecked(address), "untrn"),
+                &TopUp {
+                    height: env.block.height - 5,
+                    time: env.block.time.minus_seconds(30),
+                    amount: Uint128::from(100_u128),
+                },
+            )
+            .unwrap();
+    }
+    // cooldown ending beyond representable height or time never passes instead of overflowing
+    let response: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), env, QueryMsg::SimulateDistribute {}).unwrap()).unwrap();
+    assert_eq!(
+        response.skipped,
+        vec![
+            SkippedTarget {
+                address: "address1".to_string(),
+                denom: "untrn".to_string(),
+                reason: SkipReason::Cooldown,
+            },
+            SkippedTarget {
+                address: "address2".to_string(),
+                denom: "untrn".to_string(),
+                reason: SkipReason::Cooldown,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_distribute_spending_cap() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let update_options = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: Some(SpendingCap {
+            amount: Uint128::from(150_u128),
+            period: Duration::Height(100),
+        }),
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: update_options.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options,
+                remote: None,
+            },
+        ],
+    );
+    let env = mock_env();
+    TOP_UP_HISTORY
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address1"), "untrn"),
+            &vec![
+                TopUp {
+                    height: env.block.height - 200,
+                    time: env.block.time.minus_seconds(1200),
+                    amount: Uint128::from(100_u128),
+                },
+                TopUp {
+                    height: env.block.height - 10,
+                    time: env.block.time.minus_seconds(60),
+                    amount: Uint128::from(100_u128),
+                },
+            ],
+        )
+        .unwrap();
+    TOP_UP_HISTORY
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address2"), "untrn"),
+            &vec![TopUp {
+                height: env.block.height - 50,
+                time: env.block.time.minus_seconds(300),
+                amount: Uint128::from(150_u128),
+            }],
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_submessages(vec![SubMsg {
+                id: 0_u64,
+                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "address1".to_string(),
+                    amount: vec![cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(50_u128)
+                    }]
+                }),
+                gas_limit: None,
+                reply_on: cosmwasm_std::ReplyOn::Never
+            }])
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "50untrn"),
+                        attr("remaining_balance", "950untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "partially_funded", 0, 100, 50),
+                distribute_target_event("address2", "untrn", "spending_cap_reached", 0, 100, 0),
+            ])
+    );
+    assert_eq!(
+        TOP_UP_HISTORY
+            .load(
+                deps.as_ref().storage,
+                (&Addr::unchecked("address1"), "untrn")
+            )
+            .unwrap(),
+        vec![
+            TopUp {
+                height: env.block.height - 10,
+                time: env.block.time.minus_seconds(60),
+                amount: Uint128::from(100_u128),
+            },
+            TopUp {
+                height: env.block.height,
+                time: env.block.time,
+                amount: Uint128::from(50_u128),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_spending_allowances() {
+    let mut deps = mock_dependencies(&[]);
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: Some(SpendingCap {
+                        amount: Uint128::from(150_u128),
+                        period: Duration::Time(3600),
+                    }),
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(100_u64),
+                    threshold_balance: Uint128::from(10_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ],
+    );
+    let env = mock_env();
+    TOP_UP_HISTORY
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address1"), "untrn"),
+            &vec![
+                TopUp {
+                    height: env.block.height - 1000,
+                    time: env.block.time.minus_seconds(6000),
+                    amount: Uint128::from(100_u128),
+                },
+                TopUp {
+                    height: env.block.height - 100,
+                    time: env.block.time.minus_seconds(600),
+                    amount: Uint128::from(40_u128),
+                },
+            ],
+        )
+        .unwrap();
+    let query_res: Vec<SpendingAllowance> = from_json(
+        query(
+            deps.as_ref(),
+            env,
+            QueryMsg::SpendingAllowances {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![
+            SpendingAllowance {
+                address: "address1".to_string(),
+                denom: "untrn".to_string(),
+                spent: Uint128::from(40_u128),
+                remaining: Some(Uint128::from(110_u128)),
+            },
+            SpendingAllowance {
+                address: "address2".to_string(),
+                denom: "untrn".to_string(),
+                spent: Uint128::zero(),
+                remaining: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_query_simulate_distribute() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let expected_params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params.clone(),
+                remote: None,
+            },
+            TargetBalance {
+                address: "address3".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string(),
+                },
+                update_options: expected_params,
+                remote: None,
+            },
+        ],
+    );
+    deps.querier.add_bank_query_response(
+        "address1".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    deps.querier.add_bank_query_response(
+        "address3".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(50_u128),
+            },
+        },
+    );
+    let response: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SimulateDistribute {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        response,
+        SimulateDistributeResponse {
+            transfers: vec![PlannedTransfer {
+                address: "address1".to_string(),
+                amount: cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(91_u128),
+                },
+                deficit: Uint128::from(91_u128),
+            }],
+            skipped: vec![
+                SkippedTarget {
+                    address: "address2".to_string(),
+                    denom: "untrn".to_string(),
+                    reason: SkipReason::InsufficientFunds,
+                },
+                SkippedTarget {
+                    address: "address3".to_string(),
+                    denom: "untrn".to_string(),
+                    reason: SkipReason::Healthy,
+                },
+            ],
+            remaining_balances: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(59_u128),
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_query_simulate_distribute_no_targets() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let response: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SimulateDistribute {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        response,
+        SimulateDistributeResponse {
+            transfers: vec![],
+            skipped: vec![],
+            remaining_balances: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_query_target_statuses() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(150_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let params = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(10_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    let target = |address: &str| TargetBalance {
+        address: address.to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: params.clone(),
+        remote: None,
+    };
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![target("address1"), target("address2")],
+    );
+    let top_up = TopUp {
+        height: 1,
+        time: mock_env().block.time.minus_seconds(60),
+        amount: Uint128::from(100_u128),
+    };
+    LAST_TOP_UPS
+        .save(
+            deps.as_mut().storage,
+            (&Addr::unchecked("address1"), "untrn"),
+            &top_up,
+        )
+        .unwrap();
+    deps.querier.add_bank_query_response(
+        "address1".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(9_u128),
+            },
+        },
+    );
+    deps.querier.add_bank_query_response(
+        "address2".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: UNTRN_DENOM.to_string(),
+                amount: Uint128::from(50_u128),
+            },
+        },
+    );
+    let address1_status = TargetStatus {
+        target: target("address1"),
+        balance: Uint128::from(9_u128),
+        below_threshold: true,
+        deficit: Uint128::from(91_u128),
+        max_amount: Uint128::from(91_u128),
+        skip_reason: None,
+        last_top_up: Some(top_up),
+    };
+    let statuses: Vec<TargetStatus> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetStatuses {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        statuses,
+        vec![
+            address1_status.clone(),
+            TargetStatus {
+                target: target("address2"),
+                balance: Uint128::from(50_u128),
+                below_threshold: false,
+                deficit: Uint128::zero(),
+                max_amount: Uint128::zero(),
+                skip_reason: Some(SkipReason::Healthy),
+                last_top_up: None,
+            },
+        ]
+    );
+    let statuses: Vec<TargetStatus> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetStatuses {
+                start_after: Some((Addr::unchecked("address1"), "untrn".to_string())),
+                limit: Some(1),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        statuses
+            .iter()
+            .map(|status| status.target.address.as_str())
+            .collect::<Vec<_>>(),
+        vec!["address2"]
+    );
+    let status: TargetStatus = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::TargetStatus {
+                address: Addr::unchecked("address1"),
+                denom: "untrn".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(status, address1_status);
+    let error = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::TargetStatus {
+            address: Addr::unchecked("address3"),
+            denom: "untrn".to_string(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(error, ContractError::UnknownTargetBalance);
+}
+
+#[test]
+fn test_query_simulate_distribute_remote_balance_unavailable() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "ibc/uatom".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let key = (&Addr::unchecked("cosmos1remote"), "ibc/uatom");
+    TARGET_BALANCES
+        .save(
+            deps.as_mut().storage,
+            key,
+            &TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(10_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+        )
+        .unwrap();
+    REMOTE_TARGETS
+        .save(deps.as_mut().storage, key, &remote_target())
+        .unwrap();
+    let query_res: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::SimulateDistribute {}).unwrap())
+            .unwrap();
+    assert_eq!(
+        query_res,
+        SimulateDistributeResponse {
+            transfers: vec![],
+            skipped: vec![SkippedTarget {
+                address: "cosmos1remote".to_string(),
+                denom: "ibc/uatom".to_string(),
+                reason: SkipReason::BalanceUnavailable,
+            }],
+            remaining_balances: vec![cosmwasm_std::Coin {
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(1000_u128),
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_distribute_remote_target() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "ibc/uatom".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(5000_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let key = (&Addr::unchecked(REMOTE_ADDRESS), "ibc/uatom");
+    TARGET_BALANCES
+        .save(
+            deps.as_mut().storage,
+            key,
+            &TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(60_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+        )
+        .unwrap();
+    REMOTE_TARGETS
+        .save(deps.as_mut().storage, key, &remote_target())
+        .unwrap();
+    REMOTE_BALANCE_QUERIES
+        .save(deps.as_mut().storage, key, &1)
+        .unwrap();
+    mock_remote_balance(&mut deps.querier, 1, 50, 12340);
+    mock_min_ibc_fee(&mut deps.querier);
+    let env = mock_env();
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(NeutronMsg::IbcTransfer {
+                source_port: "transfer".to_string(),
+                source_channel: "channel-0".to_string(),
+                token: cosmwasm_std::Coin {
+                    denom: "ibc/uatom".to_string(),
+                    amount: Uint128::from(50_u128),
+                },
+                sender: "cosmos2contract".to_string(),
+                receiver: REMOTE_ADDRESS.to_string(),
+                timeout_height: RequestPacketTimeoutHeight {
+                    revision_number: None,
+                    revision_height: None,
+                },
+                timeout_timestamp: env.block.time.plus_seconds(600).nanos(),
+                memo: "".to_string(),
+                fee: min_ibc_fee(),
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "50ibc/uatom"),
+                        attr("remaining_balance", "950ibc/uatom")
+                    ])
+            )
+            .add_event(distribute_target_event(
+                REMOTE_ADDRESS,
+                "ibc/uatom",
+                "funded",
+                50,
+                50,
+                50
+            ))
+    );
+}
+
+#[test]
+fn test_distribute_remote_target_stale_balance() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "ibc/uatom".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    let key = (&Addr::unchecked(REMOTE_ADDRESS), "ibc/uatom");
+    TARGET_BALANCES
+        .save(
+            deps.as_mut().storage,
+            key,
+            &TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(60_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+        )
+        .unwrap();
+    REMOTE_TARGETS
+        .save(deps.as_mut().storage, key, &remote_target())
+        .unwrap();
+    REMOTE_BALANCE_QUERIES
+        .save(deps.as_mut().storage, key, &1)
+        .unwrap();
+    let env = mock_env();
+    LAST_TOP_UPS
+        .save(
+            deps.as_mut().storage,
+            key,
+            &TopUp {
+                height: env.block.height - 5,
+                time: env.block.time.minus_seconds(30),
+                amount: Uint128::from(50_u128),
+            },
+        )
+        .unwrap();
+    // result was submitted before the last transfer could land
+    mock_remote_balance(&mut deps.querier, 1, 50, env.block.height - 5);
+    let query_res: SimulateDistributeResponse =
+        from_json(query(deps.as_ref(), env, QueryMsg::SimulateDistribute {}).unwrap()).unwrap();
+    assert_eq!(
+        query_res,
+        SimulateDistributeResponse {
+            transfers: vec![],
+            skipped: vec![SkippedTarget {
+                address: REMOTE_ADDRESS.to_string(),
+                denom: "ibc/uatom".to_string(),
+                reason: SkipReason::StaleBalance,
+            }],
+            remaining_balances: vec![cosmwasm_std::Coin {
+                denom: "ibc/uatom".to_string(),
+                amount: Uint128::from(1000_u128),
+            }],
+        }
+    );
+}
+
+#[test]
+fn test_distribute_remote_target_outdated_balance() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "ibc/uatom".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    CONFIG
+        .save(
+            deps.as_mut().storage,
+            &Config {
+                max_balance_age: 50,
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let update_options = TargetBalanceUpdateParams {
+        target_balance: Uint128::from(100_u64),
+        threshold_balance: Uint128::from(60_u64),
+        priority: 0,
+        cooldown: None,
+        spending_cap: None,
+        sponsor: None,
+    };
+    let env = mock_env();
+    for (address, query_id, local_height) in [
+        ("cosmos1fresh", 1, env.block.height - 50),
+        ("cosmos1outdated", 2, env.block.height - 51),
+    ] {
+        let key = (&Addr::unchecked(address), "ibc/uatom");
+        TARGET_BALANCES
+            .save(deps.as_mut().storage, key, &update_options)
+            .unwrap();
+        REMOTE_TARGETS
+            .save(deps.as_mut().storage, key, &remote_target())
+            .unwrap();
+        REMOTE_BALANCE_QUERIES
+            .save(deps.as_mut().storage, key, &query_id)
+            .unwrap();
+        mock_remote_balance(&mut deps.querier, query_id, 100, local_height);
+    }
+    let query_res: Vec<StaleTarget> = from_json(
+        query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::StaleTargets {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![StaleTarget {
+            address: "cosmos1outdated".to_string(),
+            denom: "ibc/uatom".to_string(),
+            last_submitted_height: env.block.height - 51,
+        }]
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.events,
+        vec![
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                .add_attributes(vec![
+                    attr("funded_targets", "0"),
+                    attr("total_sent", "0ibc/uatom"),
+                    attr("remaining_balance", "1000ibc/uatom")
+                ]),
+            distribute_target_event("cosmos1fresh", "ibc/uatom", "healthy", 100, 0, 0),
+            distribute_target_event("cosmos1outdated", "ibc/uatom", "stale_balance", 0, 100, 0),
+        ]
+    );
+}
+
+#[test]
+fn test_sudo_ibc_transfer_callbacks() {
+    let mut deps = mock_dependencies(&[]);
+    let request = RequestPacket {
+        sequence: Some(7),
+        source_port: Some("transfer".to_string()),
+        source_channel: Some("channel-0".to_string()),
+        destination_port: None,
+        destination_channel: None,
+        data: None,
+        timeout_height: None,
+        timeout_timestamp: None,
+    };
+    let sudo_res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Timeout {
+            request: request.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        sudo_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-sudo-timeout")
+                .add_attributes(vec![attr("channel", "channel-0"), attr("sequence", "7")])
+        )
+    );
+    let sudo_res = sudo(
+        deps.as_mut(),
+        mock_env(),
+        SudoMsg::Error {
+            request,
+            details: "ack error".to_string(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        sudo_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-sudo-error").add_attributes(
+                vec![
+                    attr("channel", "channel-0"),
+                    attr("sequence", "7"),
+                    attr("details", "ack error")
+                ]
+            )
+        )
+    );
+}
+
+#[test]
+fn test_execute_update_config() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                shortfall_policy: Some(ShortfallPolicy::ProRata),
+                distribute_allowlist: None,
+                ibc_transfer_timeout: None,
+                balance_query_update_period: None,
+                max_balance_age: Some(50),
+                accepted_denoms: None,
+                timelock: None,
+                withdraw_recipients: None,
+                withdraw_limits: None,
+            },
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-update-config")
+                .add_attributes(vec![
+                    attr("shortfall_policy", "ProRata"),
+                    attr("max_balance_age", "50")
+                ])
+        )
+    );
+    let config: Config =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            shortfall_policy: ShortfallPolicy::ProRata,
+            distribute_allowlist: vec![],
+            ibc_transfer_timeout: 600,
+            balance_query_update_period: 100,
+            max_balance_age: 50,
+            accepted_denoms: vec![],
+            timelock: None,
+            withdraw_recipients: vec![],
+            withdraw_limits: vec![],
+        }
+    );
+}
+
+#[test]
+fn test_execute_update_config_balance_query_update_period() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    for (address, query_id) in [("cosmos1remote1", 1_u64), ("cosmos1remote2", 2_u64)] {
+        REMOTE_BALANCE_QUERIES
+            .save(
+                deps_mut.storage,
+                (&Addr::unchecked(address), "ibc/uatom"),
+                &query_id,
+            )
+            .unwrap();
+    }
+    let update_period = |balance_query_update_period: u64| ExecuteMsg::UpdateConfig {
+        new_config: ConfigOptional {
+            balance_query_update_period: Some(balance_query_update_period),
+            ..ConfigOptional::default()
+        },
+    };
+    // unchanged period doesn't touch registered queries
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_period(100),
+    )
+    .unwrap();
+    assert_eq!(execute_res.messages, vec![]);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_period(50),
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![1_u64, 2_u64]
+            .into_iter()
+            .map(|query_id| SubMsg::new(NeutronMsg::UpdateInterchainQuery {
+                query_id,
+                new_keys: None,
+                new_update_period: Some(50),
+                new_transactions_filter: None,
+            }))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_execute_update_config_unauthorized() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::UpdateConfig {
+            new_config: ConfigOptional {
+                shortfall_policy: Some(ShortfallPolicy::ProRata),
+                distribute_allowlist: None,
+                ibc_transfer_timeout: None,
+                balance_query_update_period: None,
+                max_balance_age: None,
+                accepted_denoms: None,
+                timelock: None,
+                withdraw_recipients: None,
+                withdraw_limits: None,
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+}
+
+#[test]
+fn test_execute_add_cron_schedule() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let env = mock_env();
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddCronSchedule {
+            name: "gas-distributor".to_string(),
+            period: 100,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(NeutronMsg::AddSchedule {
+                name: "gas-distributor".to_string(),
+                period: 100,
+                msgs: vec![MsgExecuteContract {
+                    contract: env.contract.address.to_string(),
+                    msg: "{\"distribute\":{}}".to_string(),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-add-cron-schedule")
+                    .add_attributes(vec![attr("name", "gas-distributor"), attr("period", "100")])
+            )
+    );
+    let cron_schedule: Option<CronSchedule> =
+        from_json(query(deps.as_ref(), env.clone(), QueryMsg::CronSchedule {}).unwrap()).unwrap();
+    assert_eq!(
+        cron_schedule,
+        Some(CronSchedule {
+            name: "gas-distributor".to_string(),
+            period: 100,
+        })
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::AddCronSchedule {
+            name: "gas-distributor".to_string(),
+            period: 100,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::CronScheduleAlreadyExists);
+}
+
+#[test]
+fn test_execute_add_cron_schedule_unauthorized() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::AddCronSchedule {
+            name: "gas-distributor".to_string(),
+            period: 100,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+}
+
+#[test]
+fn test_execute_remove_cron_schedule() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CRON_SCHEDULE
+        .save(
+            deps_mut.storage,
+            &CronSchedule {
+                name: "gas-distributor".to_string(),
+                period: 100,
+            },
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveCronSchedule {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(NeutronMsg::RemoveSchedule {
+                name: "gas-distributor".to_string(),
+            })
+            .add_event(
+                Event::new(
+                    "crates.io:drop-helper__drop-gas-distributor-execute-remove-cron-schedule"
+                )
+                .add_attributes(vec![attr("name", "gas-distributor")])
+            )
+    );
+    assert!(!CRON_SCHEDULE.exists(deps.as_ref().storage));
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveCronSchedule {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::CronScheduleNotFound);
+}
+
+fn refill_config() -> RefillConfig {
+    RefillConfig {
+        router: Addr::unchecked("router"),
+        offer_denom: "ibc/usdc".to_string(),
+        offer_amount: Uint128::from(500_u128),
+        floor: Uint128::from(1000_u128),
+        max_slippage: Decimal::percent(5),
+        min_price: Decimal::from_ratio(5_u128, 1_u128),
+        cooldown: Duration::Height(100),
+    }
+}
+
+#[test]
+fn test_execute_set_refill_config() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetRefillConfig {
+            refill_config: refill_config(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-set-refill-config")
+                .add_attributes(vec![
+                    attr("router", "router"),
+                    attr("offer_denom", "ibc/usdc"),
+                    attr("offer_amount", "500"),
+                    attr("floor", "1000"),
+                    attr("max_slippage", "0.05"),
+                    attr("min_price", "5"),
+                    attr("cooldown", "Height(100)"),
+                ])
+        )
+    );
+    let query_res: Option<RefillConfig> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::RefillConfig {}).unwrap()).unwrap();
+    assert_eq!(query_res, Some(refill_config()));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveRefillConfig {},
+    )
+    .unwrap();
+    let query_res: Option<RefillConfig> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::RefillConfig {}).unwrap()).unwrap();
+    assert_eq!(query_res, None);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveRefillConfig {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::RefillConfigNotFound);
+}
+
+#[test]
+fn test_execute_set_refill_config_invalid() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    for (refill_config, error) in [
+        (
+            RefillConfig {
+                offer_denom: "untrn".to_string(),
+                ..refill_config()
+            },
+            ContractError::InvalidRefillDenom {
+                denom: "untrn".to_string(),
+            },
+        ),
+        (
+            RefillConfig {
+                offer_amount: Uint128::zero(),
+                ..refill_config()
+            },
+            ContractError::ZeroRefillAmount,
+        ),
+        (
+            RefillConfig {
+                max_slippage: Decimal::one(),
+                ..refill_config()
+            },
+            ContractError::InvalidMaxSlippage,
+        ),
+        (
+            RefillConfig {
+                min_price: Decimal::zero(),
+                ..refill_config()
+            },
+            ContractError::ZeroRefillPrice,
+        ),
+    ] {
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetRefillConfig { refill_config },
+        )
+        .unwrap_err();
+        assert_eq!(execute_res, error);
+    }
+}
+
+#[test]
+fn test_distribute_refill() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(1000_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(10_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: None,
+            },
+            remote: None,
+        }],
+    );
+    deps.querier.add_wasm_query_response("router", |msg| {
+        let query: RouterQueryMsg = from_json(msg).unwrap();
+        assert_eq!(
+            query,
+            RouterQueryMsg::SimulateSwapOperations {
+                offer_amount: Uint128::from(300_u128),
+                operations: vec![SwapOperation::AstroSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: "ibc/usdc".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "untrn".to_string(),
+                    },
+                }],
+            }
+        );
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(2000_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: "address1".to_string(),
+                amount: vec![cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(100_u128),
+                }],
+            })
+            .add_message(WasmMsg::Execute {
+                contract_addr: "router".to_string(),
+                msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                    operations: vec![SwapOperation::AstroSwap {
+                        offer_asset_info: AssetInfo::NativeToken {
+                            denom: "ibc/usdc".to_string(),
+                        },
+                        ask_asset_info: AssetInfo::NativeToken {
+                            denom: "untrn".to_string(),
+                        },
+                    }],
+                    minimum_receive: Some(Uint128::from(1900_u128)),
+                    to: None,
+                    max_spread: Some(Decimal::percent(5)),
+                })
+                .unwrap(),
+                funds: vec![cosmwasm_std::Coin {
+                    denom: "ibc/usdc".to_string(),
+                    amount: Uint128::from(300_u128),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "900untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "funded", 0, 100, 100),
+                Event::new("crates.io:drop-helper__drop-gas-distributor-refill-swap")
+                    .add_attributes(vec![
+                        attr("router", "router"),
+                        attr("offer", "300ibc/usdc"),
+                        attr("simulated_amount", "2000"),
+                        attr("minimum_receive", "1900"),
+                        attr("untrn_balance", "900"),
+                    ]),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_refill_sponsored_balance() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(900_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    save_sponsor_balance(deps.as_mut().storage, "sponsor1", "ibc/usdc", 200);
+    deps.querier.add_wasm_query_response("router", |msg| {
+        let query: RouterQueryMsg = from_json(msg).unwrap();
+        // sponsor sub-account isn't offered to the router
+        assert!(matches!(
+            query,
+            RouterQueryMsg::SimulateSwapOperations { offer_amount, .. }
+                if offer_amount == Uint128::from(100_u128)
+        ));
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(700_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "router".to_string(),
+            msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations: vec![SwapOperation::AstroSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: "ibc/usdc".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "untrn".to_string(),
+                    },
+                }],
+                minimum_receive: Some(Uint128::from(665_u128)),
+                to: None,
+                max_spread: Some(Decimal::percent(5)),
+            })
+            .unwrap(),
+            funds: vec![cosmwasm_std::Coin {
+                denom: "ibc/usdc".to_string(),
+                amount: Uint128::from(100_u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn test_distribute_refill_sponsored_floor() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(2000_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    save_sponsor_balance(deps.as_mut().storage, "sponsor1", "untrn", 1200);
+    save_target_balances(
+        deps.as_mut().storage,
+        vec![TargetBalance {
+            address: "address1".to_string(),
+            token: Token::Native {
+                denom: "untrn".to_string(),
+            },
+            update_options: TargetBalanceUpdateParams {
+                target_balance: Uint128::from(100_u64),
+                threshold_balance: Uint128::from(10_u64),
+                priority: 0,
+                cooldown: None,
+                spending_cap: None,
+                sponsor: Some(Addr::unchecked("sponsor1")),
+            },
+            remote: None,
+        }],
+    );
+    deps.querier.add_wasm_query_response("router", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(2000_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new()
+            .add_message(BankMsg::Send {
+                to_address: "address1".to_string(),
+                amount: vec![cosmwasm_std::Coin {
+                    denom: "untrn".to_string(),
+                    amount: Uint128::from(100_u128),
+                }],
+            })
+            .add_message(WasmMsg::Execute {
+                contract_addr: "router".to_string(),
+                msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                    operations: vec![SwapOperation::AstroSwap {
+                        offer_asset_info: AssetInfo::NativeToken {
+                            denom: "ibc/usdc".to_string(),
+                        },
+                        ask_asset_info: AssetInfo::NativeToken {
+                            denom: "untrn".to_string(),
+                        },
+                    }],
+                    minimum_receive: Some(Uint128::from(1900_u128)),
+                    to: None,
+                    max_spread: Some(Decimal::percent(5)),
+                })
+                .unwrap(),
+                funds: vec![cosmwasm_std::Coin {
+                    denom: "ibc/usdc".to_string(),
+                    amount: Uint128::from(300_u128),
+                }],
+            })
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                    .add_attributes(vec![
+                        attr("funded_targets", "1"),
+                        attr("total_sent", "100untrn"),
+                        attr("remaining_balance", "1900untrn")
+                    ])
+            )
+            .add_events(vec![
+                distribute_target_event("address1", "untrn", "funded", 0, 100, 100),
+                // 1100untrn left in the sponsor sub-account don't count towards the floor
+                Event::new("crates.io:drop-helper__drop-gas-distributor-refill-swap")
+                    .add_attributes(vec![
+                        attr("router", "router"),
+                        attr("offer", "300ibc/usdc"),
+                        attr("simulated_amount", "2000"),
+                        attr("minimum_receive", "1900"),
+                        attr("untrn_balance", "800"),
+                    ]),
+            ])
+    );
+}
+
+#[test]
+fn test_distribute_refill_above_floor() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(2000_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert!(execute_res.messages.is_empty());
+}
+
+#[test]
+fn test_distribute_refill_min_price() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(900_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    // pool moved below the minimal price, 300 * 5 = 1500 untrn is required
+    deps.querier.add_wasm_query_response("router", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(1499_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert!(execute_res.messages.is_empty());
+    assert_eq!(LAST_REFILL.may_load(deps.as_ref().storage).unwrap(), None);
+    // slippage would allow less than the minimal price, so the price bound is required instead
+    deps.querier.add_wasm_query_response("router", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(1550_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(WasmMsg::Execute {
+            contract_addr: "router".to_string(),
+            msg: to_json_binary(&RouterExecuteMsg::ExecuteSwapOperations {
+                operations: vec![SwapOperation::AstroSwap {
+                    offer_asset_info: AssetInfo::NativeToken {
+                        denom: "ibc/usdc".to_string(),
+                    },
+                    ask_asset_info: AssetInfo::NativeToken {
+                        denom: "untrn".to_string(),
+                    },
+                }],
+                minimum_receive: Some(Uint128::from(1500_u128)),
+                to: None,
+                max_spread: Some(Decimal::percent(5)),
+            })
+            .unwrap(),
+            funds: vec![cosmwasm_std::Coin {
+                denom: "ibc/usdc".to_string(),
+                amount: Uint128::from(300_u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn test_distribute_refill_cooldown() {
+    let mut deps = mock_dependencies(&[
+        cosmwasm_std::Coin {
+            denom: "untrn".to_string(),
+            amount: Uint128::from(900_u128),
+        },
+        cosmwasm_std::Coin {
+            denom: "ibc/usdc".to_string(),
+            amount: Uint128::from(300_u128),
+        },
+    ]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    REFILL_CONFIG
+        .save(deps.as_mut().storage, &refill_config())
+        .unwrap();
+    deps.querier.add_wasm_query_response("router", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(2000_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(execute_res.messages.len(), 1);
+    assert_eq!(
+        LAST_REFILL.load(deps.as_ref().storage).unwrap(),
+        TopUp {
+            height: mock_env().block.height,
+            time: mock_env().block.time,
+            amount: Uint128::from(300_u128),
+        }
+    );
+    let mut env = mock_env();
+    env.block.height += 99;
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert!(execute_res.messages.is_empty());
+    env.block.height += 1;
+    deps.querier.add_wasm_query_response("router", |_| {
+        to_json_binary(&SimulateSwapOperationsResponse {
+            amount: Uint128::from(2000_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert_eq!(execute_res.messages.len(), 1);
+}
+
+fn funding_source(kind: FundingSourceKind) -> FundingSource {
+    FundingSource {
+        kind,
+        amount: Uint128::from(500_u128),
+        cap: SpendingCap {
+            amount: Uint128::from(800_u128),
+            period: Duration::Height(100),
+        },
+    }
+}
+
+#[test]
+fn test_execute_set_funding_source() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let source = funding_source(FundingSourceKind::Treasury {
+        contract: Addr::unchecked("treasury"),
+        denom: "untrn".to_string(),
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetFundingSource {
+            funding_source: source.clone(),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-set-funding-source")
+                .add_attributes(vec![
+                    attr("source", "treasury"),
+                    attr("amount", "500"),
+                    attr("cap", "800"),
+                ])
+        )
+    );
+    let query_res: Option<FundingSource> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::FundingSource {}).unwrap()).unwrap();
+    assert_eq!(query_res, Some(source));
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveFundingSource {},
+    )
+    .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::RemoveFundingSource {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::FundingSourceNotFound);
+}
+
+#[test]
+fn test_execute_set_funding_source_zero_cap() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetFundingSource {
+            funding_source: FundingSource {
+                cap: SpendingCap {
+                    amount: Uint128::zero(),
+                    period: Duration::Height(100),
+                },
+                ..funding_source(FundingSourceKind::Cw20Allowance {
+                    contract: Addr::unchecked("token"),
+                    owner: Addr::unchecked("treasury"),
+                })
+            },
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::ZeroPullCap);
+}
+
+#[test]
+fn test_execute_set_funding_source_invalid_denom() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    for denom in ["", "cw20:token"] {
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::SetFundingSource {
+                funding_source: funding_source(FundingSourceKind::Treasury {
+                    contract: Addr::unchecked("treasury"),
+                    denom: denom.to_string(),
+                }),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            execute_res,
+            ContractError::InvalidPullDenom {
+                denom: denom.to_string(),
+            }
+        );
+    }
+}
+
+#[test]
+fn test_distribute_pull_cw20_allowance() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    FUNDING_SOURCE
+        .save(
+            deps.as_mut().storage,
+            &funding_source(FundingSourceKind::Cw20Allowance {
+                contract: Addr::unchecked("token"),
+                owner: Addr::unchecked("treasury"),
+            }),
+        )
+        .unwrap();
+    let env = mock_env();
+    PULL_HISTORY
+        .save(
+            deps.as_mut().storage,
+            &vec![TopUp {
+                height: env.block.height - 10,
+                time: env.block.time.minus_seconds(60),
+                amount: Uint128::from(500_u128),
+            }],
+        )
+        .unwrap();
+    deps.querier.add_wasm_query_response("token", |_| {
+        to_json_binary(&AllowanceResponse {
+            allowance: Uint128::from(1000_u128),
+            expires: Expiration::Never {},
+        })
+        .unwrap()
+    });
+    deps.querier.add_wasm_query_response("token", |_| {
+        to_json_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(100_u128),
+        })
+        .unwrap()
+    });
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    // only 300 is left within the cap
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "token".to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: "treasury".to_string(),
+                    recipient: "cosmos2contract".to_string(),
+                    amount: Uint128::from(300_u128),
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            2
+        )]
+    );
+    assert_eq!(
+        execute_res.events,
+        vec![
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
+                .add_attributes(vec![attr("funded_targets", "0")]),
+            Event::new("crates.io:drop-helper__drop-gas-distributor-pull-funds").add_attributes(
+                vec![
+                    attr("source", "token"),
+                    attr("denom", "cw20:token"),
+                    attr("amount", "300"),
+                ]
+            ),
+        ]
+    );
+    assert_eq!(
+        PENDING_PULL.load(deps.as_ref().storage).unwrap(),
+        ("cw20:token".to_string(), Uint128::from(100_u128))
+    );
+    deps.querier.add_wasm_query_response("token", |_| {
+        to_json_binary(&Cw20BalanceResponse {
+            balance: Uint128::from(400_u128),
+        })
+        .unwrap()
+    });
+    let reply_res = reply(
+        deps.as_mut(),
+        env,
+        Reply {
+            id: 2,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        reply_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-reply-pull-funds")
+                .add_attributes(vec![
+                    attr("denom", "cw20:token"),
+                    attr("received", "300"),
+                    attr("pulled_within_period", "800"),
+                ])
+        )
+    );
+    assert_eq!(PULL_HISTORY.load(deps.as_ref().storage).unwrap().len(), 2);
+    assert_eq!(PENDING_PULL.may_load(deps.as_ref().storage).unwrap(), None);
+}
+
+#[test]
+fn test_distribute_pull_cw20_allowance_unavailable() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    FUNDING_SOURCE
+        .save(
+            deps.as_mut().storage,
+            &funding_source(FundingSourceKind::Cw20Allowance {
+                contract: Addr::unchecked("token"),
+                owner: Addr::unchecked("treasury"),
+            }),
+        )
+        .unwrap();
+    // source contract isn't a cw20 token anymore, so it doesn't answer allowance query
+    deps.querier
+        .add_wasm_query_response("token", |_| to_json_binary(&"unknown query").unwrap());
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert!(execute_res.messages.is_empty());
+    assert_eq!(PULL_HISTORY.may_load(deps.as_ref().storage).unwrap(), None);
+}
+
+#[test]
+fn test_distribute_pull_treasury() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(100_u128),
+    }]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    FUNDING_SOURCE
+        .save(
+            deps.as_mut().storage,
+            &funding_source(FundingSourceKind::Treasury {
+                contract: Addr::unchecked("treasury"),
+                denom: "untrn".to_string(),
+            }),
+        )
+        .unwrap();
+    let env = mock_env();
+    PULL_HISTORY
+        .save(
+            deps.as_mut().storage,
+            &vec![TopUp {
+                height: env.block.height - 10,
+                time: env.block.time.minus_seconds(60),
+                amount: Uint128::from(500_u128),
+            }],
+        )
+        .unwrap();
+    let pull_reply = |result| Reply { id: 2, result };
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    // treasury is asked for the rest of the cap only
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::reply_always(
+            WasmMsg::Execute {
+                contract_addr: "treasury".to_string(),
+                msg: to_json_binary(&TreasuryExecuteMsg::Withdraw {
+                    recipient: "cosmos2contract".to_string(),
+                    amount: cosmwasm_std::Coin {
+                        denom: "untrn".to_string(),
+                        amount: Uint128::from(300_u128),
+                    },
+                })
+                .unwrap(),
+                funds: vec![],
+            },
+            2
+        )]
+    );
+    // failed pull is only reported
+    let reply_res = reply(
+        deps.as_mut(),
+        env.clone(),
+        pull_reply(SubMsgResult::Err("treasury is empty".to_string())),
+    )
+    .unwrap();
+    assert_eq!(
+        reply_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-reply-pull-funds")
+                .add_attributes(vec![
+                    attr("denom", "untrn"),
+                    attr("error", "treasury is empty"),
+                ])
+        )
+    );
+    assert_eq!(PULL_HISTORY.load(deps.as_ref().storage).unwrap().len(), 1);
+    assert_eq!(PENDING_PULL.may_load(deps.as_ref().storage).unwrap(), None);
+    // treasury sent less than requested, only the received amount counts against the cap
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    deps.querier.add_bank_query_response(
+        "cosmos2contract".to_string(),
+        BalanceResponse {
+            amount: cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(250_u128),
+            },
+        },
+    );
+    let reply_res = reply(
+        deps.as_mut(),
+        env.clone(),
+        pull_reply(SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: None,
+        })),
+    )
+    .unwrap();
+    assert_eq!(
+        reply_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-reply-pull-funds")
+                .add_attributes(vec![
+                    attr("denom", "untrn"),
+                    attr("received", "150"),
+                    attr("pulled_within_period", "650"),
+                ])
+        )
+    );
+    assert_eq!(
+        PULL_HISTORY.load(deps.as_ref().storage).unwrap()[1],
+        TopUp {
+            height: env.block.height,
+            time: env.block.time,
+            amount: Uint128::from(150_u128),
+        }
+    );
+}
+
+#[test]
+fn test_distribute_pull_treasury_cap_reached() {
+    let mut deps = mock_dependencies(&[]);
+    CONFIG
+        .save(deps.as_mut().storage, &Config::default())
+        .unwrap();
+    FUNDING_SOURCE
+        .save(
+            deps.as_mut().storage,
+            &funding_source(FundingSourceKind::Treasury {
+                contract: Addr::unchecked("treasury"),
+                denom: "untrn".to_string(),
+            }),
+        )
+        .unwrap();
+    let env = mock_env();
+    PULL_HISTORY
+        .save(
+            deps.as_mut().storage,
+            &vec![TopUp {
+                height: env.block.height - 10,
+                time: env.block.time.minus_seconds(60),
+                amount: Uint128::from(800_u128),
+            }],
+        )
+        .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+    assert!(execute_res.messages.is_empty());
+}
+
+#[test]
+fn test_execute_pause_and_unpause() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG.save(deps_mut.storage, &Config::default()).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: "pauser".to_string(),
+            withdraw_limits: vec![],
+        },
+    )
+    .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pauser", &[]),
+        ExecuteMsg::Pause { withdrawals: true },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-pause").add_attributes(
+                vec![
+                    attr("paused_by", "pauser"),
+                    attr("withdrawals_paused", "true")
+                ]
+            )
+        )
+    );
+    let query_res: Option<PauseInfo> =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap();
+    assert_eq!(
+        query_res,
+        Some(PauseInfo {
+            paused_by: Addr::unchecked("pauser"),
+            height: mock_env().block.height,
+            time: mock_env().block.time,
+            withdrawals_paused: true,
+        })
+    );
+    for msg in [
+        ExecuteMsg::Distribute {},
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: None,
+            denom: None,
+        },
+    ] {
+        let execute_res =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(execute_res, ContractError::Paused);
+    }
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Pause { withdrawals: false },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::AlreadyPaused);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-unpause")
+                .add_attributes(vec![attr("unpaused_by", "owner")])
+        )
+    );
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("pauser", &[]),
+        ExecuteMsg::Unpause {},
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::NotPaused);
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Distribute {},
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_execute_pause_keeps_withdrawals() {
+    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
+        denom: "untrn".to_string(),
+        amount: Uint128::from(1000_u128),
+    }]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::Pause { withdrawals: false },
+    )
+    .unwrap();
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::WithdrawTokens {
+            recepient: None,
+            amount: Some(Uint128::from(100_u128)),
+            denom: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        execute_res.messages,
+        vec![SubMsg::new(BankMsg::Send {
+            to_address: "owner".to_string(),
+            amount: vec![cosmwasm_std::Coin {
+                denom: "untrn".to_string(),
+                amount: Uint128::from(100_u128),
+            }],
+        })]
+    );
+}
+
+#[test]
+fn test_execute_timelocked_operation() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                timelock: Some(Duration::Height(10)),
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let target_balance = TargetBalance {
+        address: "address1".to_string(),
+        token: Token::Native {
+            denom: "untrn".to_string(),
+        },
+        update_options: TargetBalanceUpdateParams {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+            priority: 0,
+            cooldown: None,
+            spending_cap: None,
+            sponsor: None,
+        },
+        remote: None,
+    };
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::AddTargetBalance {
+            target_balance: target_balance.clone(),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::TimelockRequired);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ProposeOperation {
+            operation: TimelockedOperation::AddTargetBalance {
+                target_balance: target_balance.clone(),
+            },
+        },
+    )
+    .unwrap();
+    let executable_at = Expiration::AtHeight(mock_env().block.height + 10);
+    assert_eq!(
+        execute_res,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-propose-operation")
+                .add_attributes(vec![
+                    attr("id", "0"),
+                    attr("operation", "add_target_balance"),
+                    attr("proposer", "owner"),
+                    attr("executable_at", executable_at.to_string()),
+                ])
+        )
+    );
+    let query_res: Vec<PendingOperation> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::PendingOperations {
+                start_after: None,
+                limit: None,
             },
-        },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_res,
+        vec![PendingOperation {
+            id: 0,
+            operation: TimelockedOperation::AddTargetBalance {
+                target_balance: target_balance.clone(),
+            },
+            proposer: Addr::unchecked("owner"),
+            executable_at,
+            proposed_height: mock_env().block.height,
+            proposed_time: mock_env().block.time,
+        }]
     );
     let execute_res = execute(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::Distribute {},
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::OperationNotReady { id: 0 });
+    let mut env = mock_env();
+    env.block.height += 10;
+    let execute_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 0 },
     )
     .unwrap();
-
     assert_eq!(
         execute_res,
         Response::new()
-            .add_submessages(vec![SubMsg {
-                id: 0_u64,
-                msg: cosmwasm_std::CosmosMsg::Bank(BankMsg::Send {
-                    to_address: "address1".to_string(),
-                    amount: vec![cosmwasm_std::Coin {
-                        denom: "untrn".to_string(),
-                        amount: Uint128::from(91_u128)
-                    }]
-                }),
-                gas_limit: None,
-                reply_on: cosmwasm_std::ReplyOn::Never
-            }])
             .add_event(
-                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-distribute")
-                    .add_attributes(vec![attr("address1", "91")])
+                Event::new(
+                    "crates.io:drop-helper__drop-gas-distributor-execute-add-target-balance"
+                )
+                .add_attributes(vec![attr("address", "address1"), attr("denom", "untrn")])
+            )
+            .add_event(
+                Event::new("crates.io:drop-helper__drop-gas-distributor-execute-operation")
+                    .add_attributes(vec![
+                        attr("id", "0"),
+                        attr("operation", "add_target_balance")
+                    ])
             )
     );
+    assert_eq!(load_target_balances(&deps.storage), vec![target_balance]);
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::OperationNotFound { id: 0 });
 }
 
 #[test]
-fn test_distribute_nobody() {
-    let mut deps = mock_dependencies(&[cosmwasm_std::Coin {
-        denom: "untrn".to_string(),
-        amount: Uint128::from(10_u128),
-    }]);
-    let expected_params = TargetBalanceUpdateParams {
-        target_balance: Uint128::from(100_u64),
-        threshold_balance: Uint128::from(10_u64),
-    };
-    TARGET_BALANCES
+fn test_execute_timelocked_funding_changes() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
         .save(
-            deps.as_mut().storage,
-            &vec![
-                TargetBalance {
-                    address: "address1".to_string(),
-                    update_options: expected_params.clone(),
+            deps_mut.storage,
+            &Config {
+                timelock: Some(Duration::Height(10)),
+                ..Config::default()
+            },
+        )
+        .unwrap();
+    let withdraw_limits = vec![WithdrawLimit {
+        denom: "untrn".to_string(),
+        cap: SpendingCap {
+            amount: Uint128::from(100_u128),
+            period: Duration::Height(10),
+        },
+    }];
+    let source = funding_source(FundingSourceKind::Treasury {
+        contract: Addr::unchecked("treasury"),
+        denom: "untrn".to_string(),
+    });
+    let operations = vec![
+        (
+            ExecuteMsg::SetRefillConfig {
+                refill_config: refill_config(),
+            },
+            TimelockedOperation::SetRefillConfig {
+                refill_config: refill_config(),
+            },
+        ),
+        (
+            ExecuteMsg::SetFundingSource {
+                funding_source: source.clone(),
+            },
+            TimelockedOperation::SetFundingSource {
+                funding_source: source.clone(),
+            },
+        ),
+        (
+            ExecuteMsg::GrantRole {
+                role: Role::Treasurer,
+                address: "treasurer1".to_string(),
+                withdraw_limits: withdraw_limits.clone(),
+            },
+            TimelockedOperation::GrantRole {
+                role: Role::Treasurer,
+                address: "treasurer1".to_string(),
+                withdraw_limits,
+            },
+        ),
+    ];
+    for (msg, operation) in operations {
+        let execute_res =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap_err();
+        assert_eq!(execute_res, ContractError::TimelockRequired);
+        // only the owner can propose them
+        let execute_res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("somebody", &[]),
+            ExecuteMsg::ProposeOperation {
+                operation: operation.clone(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(
+            execute_res,
+            ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
+        );
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ProposeOperation { operation },
+        )
+        .unwrap();
+    }
+    // roles that can't move funds are still granted right away
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: "pauser1".to_string(),
+            withdraw_limits: vec![],
+        },
+    )
+    .unwrap();
+    let mut env = mock_env();
+    env.block.height += 10;
+    for id in 0..3 {
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("somebody", &[]),
+            ExecuteMsg::ExecuteOperation { id },
+        )
+        .unwrap();
+    }
+    assert_eq!(
+        REFILL_CONFIG.load(deps.as_ref().storage).unwrap(),
+        refill_config()
+    );
+    assert_eq!(FUNDING_SOURCE.load(deps.as_ref().storage).unwrap(), source);
+    let query_res: Vec<WithdrawAllowance> = from_json(
+        query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::WithdrawAllowances {
+                address: Addr::unchecked("treasurer1"),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(query_res.len(), 1);
+}
+
+#[test]
+fn test_execute_operation_timelock_changed() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    let set_timelock = |storage: &mut dyn Storage, blocks: u64| {
+        CONFIG
+            .save(
+                storage,
+                &Config {
+                    timelock: Some(Duration::Height(blocks)),
+                    ..Config::default()
                 },
-                TargetBalance {
-                    address: "address2".to_string(),
-                    update_options: expected_params,
+            )
+            .unwrap();
+    };
+    let propose = |deps: DepsMut<NeutronQuery>| {
+        execute(
+            deps,
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ProposeOperation {
+                operation: TimelockedOperation::UpdateConfig {
+                    new_config: ConfigOptional {
+                        max_balance_age: Some(50),
+                        ..ConfigOptional::default()
+                    },
                 },
-            ],
+            },
         )
         .unwrap();
-    deps.querier.add_bank_query_response(
-        "address2".to_string(),
-        BalanceResponse {
-            amount: cosmwasm_std::Coin {
-                denom: UNTRN_DENOM.to_string(),
-                amount: Uint128::from(0_u128),
+    };
+    let execute_at = |deps: DepsMut<NeutronQuery>, id: u64, blocks: u64| {
+        let mut env = mock_env();
+        env.block.height += blocks;
+        execute(
+            deps,
+            env,
+            mock_info("somebody", &[]),
+            ExecuteMsg::ExecuteOperation { id },
+        )
+    };
+    // raised timelock delays already proposed operation
+    set_timelock(deps.as_mut().storage, 10);
+    propose(deps.as_mut());
+    set_timelock(deps.as_mut().storage, 20);
+    assert_eq!(
+        execute_at(deps.as_mut(), 0, 10).unwrap_err(),
+        ContractError::OperationNotReady { id: 0 }
+    );
+    execute_at(deps.as_mut(), 0, 20).unwrap();
+    // lowered timelock doesn't bring proposed operation forward
+    propose(deps.as_mut());
+    set_timelock(deps.as_mut().storage, 5);
+    assert_eq!(
+        execute_at(deps.as_mut(), 1, 10).unwrap_err(),
+        ContractError::OperationNotReady { id: 1 }
+    );
+    execute_at(deps.as_mut(), 1, 20).unwrap();
+}
+
+#[test]
+fn test_execute_cancel_operation() {
+    let mut deps = mock_dependencies(&[]);
+    let deps_mut = deps.as_mut();
+    cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
+    CONFIG
+        .save(
+            deps_mut.storage,
+            &Config {
+                timelock: Some(Duration::Time(3600)),
+                ..Config::default()
             },
+        )
+        .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::GrantRole {
+            role: Role::Pauser,
+            address: "pauser".to_string(),
+            withdraw_limits: vec![],
         },
+    )
+    .unwrap();
+    for _ in 0..2 {
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ProposeOperation {
+                operation: TimelockedOperation::UpdateConfig {
+                    new_config: ConfigOptional {
+                        timelock: Some(Duration::Time(0)),
+                        ..ConfigOptional::default()
+                    },
+                },
+            },
+        )
+        .unwrap();
+    }
+    let execute_res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::CancelOperation { id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        execute_res,
+        ContractError::OwnershipError(cw_ownable::OwnershipError::NotOwner)
     );
     let execute_res = execute(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
-        mock_info("owner", &[]),
-        ExecuteMsg::Distribute {},
+        mock_info("pauser", &[]),
+        ExecuteMsg::CancelOperation { id: 0 },
     )
     .unwrap();
-
     assert_eq!(
         execute_res,
-        Response::new().add_event(Event::new(
-            "crates.io:drop-helper__drop-gas-distributor-execute-distribute"
-        ))
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-execute-cancel-operation")
+                .add_attributes(vec![attr("id", "0"), attr("cancelled_by", "pauser")])
+        )
     );
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(3600);
+    let execute_res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 0 },
+    )
+    .unwrap_err();
+    assert_eq!(execute_res, ContractError::OperationNotFound { id: 0 });
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("somebody", &[]),
+        ExecuteMsg::ExecuteOperation { id: 1 },
+    )
+    .unwrap();
+    assert_eq!(CONFIG.load(deps.as_ref().storage).unwrap().timelock, None);
 }
 
 #[test]
@@ -708,15 +6719,8 @@ fn test_query_ownership() {
     let mut deps = mock_dependencies(&[]);
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
-    let query_res: String = from_json(
-        query(
-            deps.as_ref().into_empty(),
-            mock_env(),
-            QueryMsg::Ownership {},
-        )
-        .unwrap(),
-    )
-    .unwrap();
+    let query_res: String =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
     assert_eq!(query_res, "owner".to_string());
 }
 
@@ -726,7 +6730,7 @@ fn test_transfer_ownership() {
     let deps_mut = deps.as_mut();
     cw_ownable::initialize_owner(deps_mut.storage, deps_mut.api, Some("owner")).unwrap();
     execute(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("owner", &[]),
         ExecuteMsg::UpdateOwnership(cw_ownable::Action::TransferOwnership {
@@ -736,20 +6740,187 @@ fn test_transfer_ownership() {
     )
     .unwrap();
     execute(
-        deps.as_mut().into_empty(),
+        deps.as_mut(),
         mock_env(),
         mock_info("new_owner", &[]),
         ExecuteMsg::UpdateOwnership(cw_ownable::Action::AcceptOwnership {}),
     )
     .unwrap();
-    let query_res: String = from_json(
-        query(
-            deps.as_ref().into_empty(),
-            mock_env(),
-            QueryMsg::Ownership {},
+    let query_res: String =
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Ownership {}).unwrap()).unwrap();
+    assert_eq!(query_res, "new_owner".to_string());
+}
+
+#[test]
+fn test_migrate_from_v1_0_0() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:drop-helper__drop-gas-distributor",
+        "1.0.0",
+    )
+    .unwrap();
+    TARGET_BALANCES_V1_0_0
+        .save(
+            deps.as_mut().storage,
+            &vec![
+                TargetBalanceV1_0_0 {
+                    address: "address1".to_string(),
+                    update_options: TargetBalanceUpdateParamsV1_0_0 {
+                        target_balance: Uint128::from(2000_u64),
+                        threshold_balance: Uint128::from(123_u64),
+                    },
+                },
+                TargetBalanceV1_0_0 {
+                    address: "address2".to_string(),
+                    update_options: TargetBalanceUpdateParamsV1_0_0 {
+                        target_balance: Uint128::from(1000_u64),
+                        threshold_balance: Uint128::from(321_u64),
+                    },
+                },
+            ],
         )
-        .unwrap(),
+        .unwrap();
+    let response = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        response,
+        Response::new().add_event(
+            Event::new("crates.io:drop-helper__drop-gas-distributor-migrate")
+                .add_attributes(vec![attr("migrate-storage-from", "1.0.0")])
+        )
+    );
+    assert_eq!(
+        load_target_balances(deps.as_ref().storage),
+        vec![
+            TargetBalance {
+                address: "address1".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string()
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(2000_u64),
+                    threshold_balance: Uint128::from(123_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+            TargetBalance {
+                address: "address2".to_string(),
+                token: Token::Native {
+                    denom: "untrn".to_string()
+                },
+                update_options: TargetBalanceUpdateParams {
+                    target_balance: Uint128::from(1000_u64),
+                    threshold_balance: Uint128::from(321_u64),
+                    priority: 0,
+                    cooldown: None,
+                    spending_cap: None,
+                    sponsor: None,
+                },
+                remote: None,
+            },
+        ]
+    );
+    assert_eq!(
+        TARGET_BALANCES_V1_0_0
+            .may_load(deps.as_ref().storage)
+            .unwrap(),
+        None
+    );
+    let contract_version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(contract_version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_migrate_from_v1_0_0_invalid_targets() {
+    let target = |address: &str| TargetBalanceV1_0_0 {
+        address: address.to_string(),
+        update_options: TargetBalanceUpdateParamsV1_0_0 {
+            target_balance: Uint128::from(2000_u64),
+            threshold_balance: Uint128::from(123_u64),
+        },
+    };
+    for (target_balances, expected_error) in [
+        (
+            vec![target("address1"), target("address1")],
+            ContractError::DuplicateTargetBalance {
+                address: "address1".to_string(),
+                denom: "untrn".to_string(),
+            },
+        ),
+        (
+            vec![target("address1"), target("INVALID")],
+            ContractError::InvalidTargetAddress {
+                address: "INVALID".to_string(),
+            },
+        ),
+    ] {
+        let mut deps = mock_dependencies(&[]);
+        cw2::set_contract_version(
+            deps.as_mut().storage,
+            "crates.io:drop-helper__drop-gas-distributor",
+            "1.0.0",
+        )
+        .unwrap();
+        TARGET_BALANCES_V1_0_0
+            .save(deps.as_mut().storage, &target_balances)
+            .unwrap();
+        let error = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(error, expected_error);
+    }
+}
+
+#[test]
+fn test_migrate_same_version() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:drop-helper__drop-gas-distributor",
+        env!("CARGO_PKG_VERSION"),
     )
     .unwrap();
-    assert_eq!(query_res, "new_owner".to_string());
+    let response = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        response,
+        Response::new().add_event(Event::new(
+            "crates.io:drop-helper__drop-gas-distributor-migrate"
+        ))
+    );
+}
+
+#[test]
+fn test_migrate_downgrade() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(
+        deps.as_mut().storage,
+        "crates.io:drop-helper__drop-gas-distributor",
+        "99.0.0",
+    )
+    .unwrap();
+    let error = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        error,
+        ContractError::MigrationDowngrade {
+            storage_version: "99.0.0".to_string(),
+            contract_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    );
+}
+
+#[test]
+fn test_migrate_wrong_contract() {
+    let mut deps = mock_dependencies(&[]);
+    cw2::set_contract_version(deps.as_mut().storage, "crates.io:another-contract", "1.0.0")
+        .unwrap();
+    let error = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        error,
+        ContractError::MigrationContractNameMismatch {
+            storage_contract_name: "crates.io:another-contract".to_string(),
+            contract_name: "crates.io:drop-helper__drop-gas-distributor".to_string(),
+        }
+    );
 }
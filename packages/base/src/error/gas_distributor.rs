@@ -1,5 +1,6 @@
 use cosmwasm_std::StdError;
 use cw_ownable::OwnershipError;
+use neutron_sdk::NeutronError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -10,6 +11,110 @@ pub enum ContractError {
     InsufficientFunds,
     #[error("Target balance doesn't exist")]
     UnknownTargetBalance,
+    #[error("Target balance for {address} in {denom} is provided more than once")]
+    DuplicateTargetBalance { address: String, denom: String },
+    #[error("Target address {address} is invalid")]
+    InvalidTargetAddress { address: String },
+    #[error("Target balance for {address} has empty denom")]
+    EmptyTargetDenom { address: String },
+    #[error("Target balance for {address} in {denom} must be greater than zero")]
+    ZeroTargetBalance { address: String, denom: String },
+    #[error("Threshold balance for {address} in {denom} must be lower than target balance")]
+    ThresholdNotBelowTarget { address: String, denom: String },
+    #[error("Invalid token {token} of target {address}")]
+    InvalidTargetToken { address: String, token: String },
+    #[error(
+        "Remote target {address} in {denom} must be a native token with connection, channel and remote denom"
+    )]
+    InvalidRemoteTarget { address: String, denom: String },
+    #[error("Target {address} in {denom} can't be paid by a sponsor")]
+    SponsorNotAllowed { address: String, denom: String },
+    #[error("Spending cap for {address} in {denom} must be greater than zero")]
+    ZeroSpendingCap { address: String, denom: String },
     #[error("{0}")]
     OwnershipError(#[from] OwnershipError),
+    #[error("{0}")]
+    NeutronError(#[from] NeutronError),
+    #[error("No funds sent")]
+    NoFunds,
+    #[error("Denom {denom} is not accepted")]
+    DenomNotAccepted { denom: String },
+    #[error("Sender is not allowed to call distribute")]
+    DistributeNotAllowed,
+    #[error("Cron schedule is already registered")]
+    CronScheduleAlreadyExists,
+    #[error("Cron schedule is not registered")]
+    CronScheduleNotFound,
+    #[error("Invalid refill denom: {denom}")]
+    InvalidRefillDenom { denom: String },
+    #[error("Refill amount must be greater than zero")]
+    ZeroRefillAmount,
+    #[error("Max slippage must be less than one")]
+    InvalidMaxSlippage,
+    #[error("Refill minimal price must be greater than zero")]
+    ZeroRefillPrice,
+    #[error("Refill is not configured")]
+    RefillConfigNotFound,
+    #[error("Pulled amount must be greater than zero")]
+    ZeroPullAmount,
+    #[error("Pull cap must be greater than zero")]
+    ZeroPullCap,
+    #[error("Invalid pulled denom: {denom}")]
+    InvalidPullDenom { denom: String },
+    #[error("Funding source is not configured")]
+    FundingSourceNotFound,
+    #[error("Withdraw limits can only be set for treasurer")]
+    WithdrawLimitsNotAllowed,
+    #[error("Role {role} is not granted to {address}")]
+    RoleNotGranted { role: String, address: String },
+    #[error("Withdraw limit in {denom} is exceeded")]
+    WithdrawLimitExceeded { denom: String },
+    #[error("Contract is paused")]
+    Paused,
+    #[error("Contract is already paused")]
+    AlreadyPaused,
+    #[error("Contract is not paused")]
+    NotPaused,
+    #[error("Withdrawal recipient {recipient} is invalid")]
+    InvalidWithdrawRecipient { recipient: String },
+    #[error("Withdrawal recipient {recipient} is not allowed")]
+    WithdrawRecipientNotAllowed { recipient: String },
+    #[error("Withdraw cap in {denom} must be greater than zero")]
+    ZeroWithdrawCap { denom: String },
+    #[error("Withdraw cap in {denom} is exceeded")]
+    WithdrawCapExceeded { denom: String },
+    #[error("Withdrawn amount of {denom} must be greater than zero")]
+    ZeroWithdrawAmount { denom: String },
+    #[error("Denom {denom} is withdrawn more than once")]
+    DuplicateWithdrawDenom { denom: String },
+    #[error("Timelock is configured, operation has to be proposed")]
+    TimelockRequired,
+    #[error("Withdraw recipients and limits can only be loosened while timelock is configured")]
+    WithdrawRestrictionsLoosened,
+    #[error("Pending operation {id} doesn't exist")]
+    OperationNotFound { id: u64 },
+    #[error("Pending operation {id} can't be executed yet")]
+    OperationNotReady { id: u64 },
+    #[error("Unknown reply id: {id}")]
+    UnknownReplyId { id: u64 },
+    #[error("No pending balance query registration")]
+    NoPendingBalanceQuery,
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+    #[error("Contract name mismatch: stored {storage_contract_name}, expected {contract_name}")]
+    MigrationContractNameMismatch {
+        storage_contract_name: String,
+        contract_name: String,
+    },
+    #[error("Can't migrate from {storage_version} down to {contract_version}")]
+    MigrationDowngrade {
+        storage_version: String,
+        contract_version: String,
+    },
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
 }
@@ -1,54 +1,806 @@
+use crate::{
+    error::gas_distributor::ContractError,
+    state::gas_distributor::{CW20_KEY_PREFIX, UNTRN_DENOM},
+};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Deps, Uint128};
+use cosmwasm_std::{ensure, Addr, Binary, Coin, Decimal, Deps, Timestamp, Uint128};
 use cw_ownable::cw_ownable_execute;
+use cw_utils::{Duration, Expiration};
+use neutron_sdk::{bindings::query::NeutronQuery, sudo::msg::RequestPacket};
 
 #[cw_serde]
 pub struct TargetBalanceUpdateParams {
     pub threshold_balance: Uint128,
     pub target_balance: Uint128,
+    /// Targets with greater priority are funded first when contract is short on funds
+    #[serde(default)]
+    pub priority: u32,
+    /// Minimal amount of blocks or seconds between two top-ups of the target
+    pub cooldown: Option<Duration>,
+    /// Maximal amount the target can receive within a rolling period
+    pub spending_cap: Option<SpendingCap>,
+    /// Sponsor whose sub-account pays for the target instead of the shared contract balance
+    pub sponsor: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct SpendingCap {
+    pub amount: Uint128,
+    pub period: Duration,
+}
+
+/// Target living on a remote chain, topped up with IBC transfers
+#[cw_serde]
+pub struct RemoteTarget {
+    /// IBC connection to the remote chain
+    pub connection_id: String,
+    /// Transfer channel on Neutron side
+    pub channel: String,
+    /// Denom of transferred tokens on the remote chain
+    pub remote_denom: String,
+}
+
+/// Token targets are topped up with
+#[cw_serde]
+pub enum Token {
+    Native { denom: String },
+    Cw20 { contract: Addr },
+}
+
+impl Token {
+    /// String the token is keyed by in storage, queries and events. cw20 tokens are keyed by
+    /// their contract address prefixed with `cw20:`
+    pub fn key(&self) -> String {
+        match self {
+            Token::Native { denom } => denom.clone(),
+            Token::Cw20 { contract } => format!("{CW20_KEY_PREFIX}{contract}"),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Self {
+        match key.strip_prefix(CW20_KEY_PREFIX) {
+            Some(contract) => Token::Cw20 {
+                contract: Addr::unchecked(contract),
+            },
+            None => Token::Native {
+                denom: key.to_string(),
+            },
+        }
+    }
 }
 
 #[cw_serde]
 pub struct TargetBalance {
+    /// Neutron address, or remote chain address if `remote` is set
     pub address: String,
+    /// Token sent from the contract
+    pub token: Token,
     pub update_options: TargetBalanceUpdateParams,
+    pub remote: Option<RemoteTarget>,
 }
 
 impl TargetBalance {
-    pub fn validate(&self, deps: Deps) {
-        deps.api.addr_validate(&self.address).unwrap();
-        assert!(self.update_options.threshold_balance < self.update_options.target_balance)
+    pub fn validate(&self, deps: Deps<NeutronQuery>) -> Result<(), ContractError> {
+        let denom = self.token.key();
+        match &self.remote {
+            // remote chain addresses can't be validated on Neutron
+            Some(remote) => {
+                ensure!(
+                    !self.address.is_empty(),
+                    ContractError::InvalidTargetAddress {
+                        address: self.address.clone(),
+                    }
+                );
+                // cw20 tokens can't be sent with plain IBC transfers
+                ensure!(
+                    matches!(self.token, Token::Native { .. })
+                        && !remote.connection_id.is_empty()
+                        && !remote.channel.is_empty()
+                        && !remote.remote_denom.is_empty(),
+                    ContractError::InvalidRemoteTarget {
+                        address: self.address.clone(),
+                        denom: denom.clone(),
+                    }
+                );
+            }
+            None => {
+                deps.api.addr_validate(&self.address).map_err(|_| {
+                    ContractError::InvalidTargetAddress {
+                        address: self.address.clone(),
+                    }
+                })?;
+            }
+        }
+        match &self.token {
+            Token::Native { denom } => {
+                ensure!(
+                    !denom.is_empty(),
+                    ContractError::EmptyTargetDenom {
+                        address: self.address.clone(),
+                    }
+                );
+                ensure!(
+                    !denom.starts_with(CW20_KEY_PREFIX),
+                    ContractError::InvalidTargetToken {
+                        address: self.address.clone(),
+                        token: denom.clone(),
+                    }
+                );
+            }
+            Token::Cw20 { contract } => {
+                deps.api.addr_validate(contract.as_str()).map_err(|_| {
+                    ContractError::InvalidTargetToken {
+                        address: self.address.clone(),
+                        token: contract.to_string(),
+                    }
+                })?;
+            }
+        }
+        ensure!(
+            !self.update_options.target_balance.is_zero(),
+            ContractError::ZeroTargetBalance {
+                address: self.address.clone(),
+                denom: denom.clone(),
+            }
+        );
+        ensure!(
+            self.update_options.threshold_balance < self.update_options.target_balance,
+            ContractError::ThresholdNotBelowTarget {
+                address: self.address.clone(),
+                denom: denom.clone(),
+            }
+        );
+        if let Some(sponsor) = &self.update_options.sponsor {
+            deps.api.addr_validate(sponsor.as_str())?;
+            // sponsor sub-accounts are only funded with native tokens, and refunds of failed
+            // IBC transfers can't be traced back to a sub-account
+            ensure!(
+                matches!(self.token, Token::Native { .. }) && self.remote.is_none(),
+                ContractError::SponsorNotAllowed {
+                    address: self.address.clone(),
+                    denom: denom.clone(),
+                }
+            );
+        }
+        if let Some(spending_cap) = &self.update_options.spending_cap {
+            ensure!(
+                !spending_cap.amount.is_zero(),
+                ContractError::ZeroSpendingCap {
+                    address: self.address.clone(),
+                    denom,
+                }
+            );
+        }
+        Ok(())
     }
 }
 
+/// Defines how contract balance is shared when it can't cover every target in given denom
+#[cw_serde]
+#[derive(Default)]
+pub enum ShortfallPolicy {
+    /// Targets that can't be topped up in full are skipped
+    #[default]
+    Skip,
+    /// Targets are topped up by priority with whatever is left, the last one may get less
+    PartialTopUp,
+    /// Targets are topped up by priority tiers, the first tier that can't be covered in full
+    /// shares remaining funds proportionally to its deficits
+    ProRata,
+}
+
+#[cw_serde]
+pub struct Config {
+    pub shortfall_policy: ShortfallPolicy,
+    /// Addresses allowed to call `Distribute` besides the owner, anyone can call it when empty
+    pub distribute_allowlist: Vec<Addr>,
+    /// Timeout of IBC transfers to remote targets in seconds
+    pub ibc_transfer_timeout: u64,
+    /// Amount of blocks between updates of balance interchain queries of remote targets
+    pub balance_query_update_period: u64,
+    /// Maximum age in blocks of balance query results remote targets are funded by
+    pub max_balance_age: u64,
+    /// Denoms `Deposit` accepts, any denom is accepted when empty
+    pub accepted_denoms: Vec<String>,
+    /// Delay timelocked operations wait for after they're proposed, applied immediately when
+    /// not set
+    pub timelock: Option<Duration>,
+    /// Addresses `WithdrawTokens` can send tokens to, any address is allowed when empty
+    pub withdraw_recipients: Vec<Addr>,
+    /// Maximal amount of every listed denom withdrawn within a rolling period, by the owner and
+    /// treasurers together
+    pub withdraw_limits: Vec<WithdrawLimit>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            shortfall_policy: ShortfallPolicy::default(),
+            distribute_allowlist: vec![],
+            ibc_transfer_timeout: 600,
+            balance_query_update_period: 100,
+            max_balance_age: 1000,
+            accepted_denoms: vec![],
+            timelock: None,
+            withdraw_recipients: vec![],
+            withdraw_limits: vec![],
+        }
+    }
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ConfigOptional {
+    pub shortfall_policy: Option<ShortfallPolicy>,
+    pub distribute_allowlist: Option<Vec<String>>,
+    pub ibc_transfer_timeout: Option<u64>,
+    pub balance_query_update_period: Option<u64>,
+    pub max_balance_age: Option<u64>,
+    pub accepted_denoms: Option<Vec<String>>,
+    /// Zero duration disables the timelock
+    pub timelock: Option<Duration>,
+    pub withdraw_recipients: Option<Vec<String>>,
+    pub withdraw_limits: Option<Vec<WithdrawLimit>>,
+}
+
+/// Refills contract `untrn` balance by swapping a treasury asset through a DEX router
+#[cw_serde]
+pub struct RefillConfig {
+    /// Router contract implementing Astroport router interface
+    pub router: Addr,
+    /// Denom of the asset swapped into `untrn`
+    pub offer_denom: String,
+    /// Maximal amount of `offer_denom` swapped at once
+    pub offer_amount: Uint128,
+    /// Swap is made once `untrn` balance left after distribution is below this amount
+    pub floor: Uint128,
+    /// Maximal allowed deviation of the swap result from the simulated one
+    pub max_slippage: Decimal,
+    /// Minimal amount of `untrn` received for one unit of `offer_denom`. Simulation runs against
+    /// the same pool state as the swap, so only this bound protects from a manipulated price
+    pub min_price: Decimal,
+    /// Minimal amount of blocks or seconds between two refill swaps
+    pub cooldown: Duration,
+}
+
+impl RefillConfig {
+    pub fn validate(&self, deps: Deps<NeutronQuery>) -> Result<(), ContractError> {
+        deps.api.addr_validate(self.router.as_str())?;
+        ensure!(
+            !self.offer_denom.is_empty() && self.offer_denom != UNTRN_DENOM,
+            ContractError::InvalidRefillDenom {
+                denom: self.offer_denom.clone(),
+            }
+        );
+        ensure!(
+            !self.offer_amount.is_zero(),
+            ContractError::ZeroRefillAmount
+        );
+        ensure!(
+            self.max_slippage < Decimal::one(),
+            ContractError::InvalidMaxSlippage
+        );
+        ensure!(!self.min_price.is_zero(), ContractError::ZeroRefillPrice);
+        Ok(())
+    }
+}
+
+/// Contract funds are pulled from during `Distribute`
+#[cw_serde]
+pub enum FundingSourceKind {
+    /// cw20 tokens `owner` allowed the contract to spend, pulled with `TransferFrom`
+    Cw20Allowance { contract: Addr, owner: Addr },
+    /// Treasury contract implementing `treasury::ExecuteMsg`, `denom` is withdrawn from it
+    Treasury { contract: Addr, denom: String },
+}
+
+#[cw_serde]
+pub struct FundingSource {
+    pub kind: FundingSourceKind,
+    /// Maximal amount pulled on every `Distribute`
+    pub amount: Uint128,
+    /// Maximal amount pulled within a rolling period
+    pub cap: SpendingCap,
+}
+
+impl FundingSource {
+    pub fn validate(&self, deps: Deps<NeutronQuery>) -> Result<(), ContractError> {
+        match &self.kind {
+            FundingSourceKind::Cw20Allowance { contract, owner } => {
+                deps.api.addr_validate(contract.as_str())?;
+                deps.api.addr_validate(owner.as_str())?;
+            }
+            FundingSourceKind::Treasury { contract, denom } => {
+                deps.api.addr_validate(contract.as_str())?;
+                ensure!(
+                    !denom.is_empty() && !denom.starts_with(CW20_KEY_PREFIX),
+                    ContractError::InvalidPullDenom {
+                        denom: denom.clone(),
+                    }
+                );
+            }
+        }
+        ensure!(!self.amount.is_zero(), ContractError::ZeroPullAmount);
+        ensure!(!self.cap.amount.is_zero(), ContractError::ZeroPullCap);
+        Ok(())
+    }
+}
+
+/// Roles the owner can grant on top of its own permissions
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Can set, add, update and remove target balances
+    TargetManager,
+    /// Can withdraw tokens within its withdraw limits
+    Treasurer,
+    /// Can call `Distribute` when it's restricted by `Config::distribute_allowlist`
+    Distributor,
+    /// Can pause and unpause the contract
+    Pauser,
+}
+
+impl Role {
+    /// Name of the role used in storage keys and events
+    pub fn key(&self) -> &'static str {
+        match self {
+            Role::TargetManager => "target_manager",
+            Role::Treasurer => "treasurer",
+            Role::Distributor => "distributor",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct WithdrawLimit {
+    pub denom: String,
+    /// Maximal amount treasurer can withdraw within a rolling period
+    pub cap: SpendingCap,
+}
+
+/// Subset of Astroport router messages used to refill the contract
+pub mod router {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::{Decimal, Uint128};
+
+    #[cw_serde]
+    pub enum AssetInfo {
+        Token { contract_addr: String },
+        NativeToken { denom: String },
+    }
+
+    #[cw_serde]
+    pub enum SwapOperation {
+        AstroSwap {
+            offer_asset_info: AssetInfo,
+            ask_asset_info: AssetInfo,
+        },
+    }
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        ExecuteSwapOperations {
+            operations: Vec<SwapOperation>,
+            minimum_receive: Option<Uint128>,
+            to: Option<String>,
+            max_spread: Option<Decimal>,
+        },
+    }
+
+    #[cw_serde]
+    pub enum QueryMsg {
+        SimulateSwapOperations {
+            offer_amount: Uint128,
+            operations: Vec<SwapOperation>,
+        },
+    }
+
+    #[cw_serde]
+    pub struct SimulateSwapOperationsResponse {
+        pub amount: Uint128,
+    }
+}
+
+/// Interface of treasury contracts funds are pulled from
+pub mod treasury {
+    use cosmwasm_schema::cw_serde;
+    use cosmwasm_std::Coin;
+
+    #[cw_serde]
+    pub enum ExecuteMsg {
+        /// Sends `amount` from the treasury to `recipient`
+        Withdraw { recipient: String, amount: Coin },
+    }
+}
+
+#[cw_serde]
+pub struct TopUp {
+    pub height: u64,
+    pub time: Timestamp,
+    pub amount: Uint128,
+}
+
+/// Neutron cron schedule registered by the contract
+#[cw_serde]
+pub struct CronSchedule {
+    pub name: String,
+    /// Amount of blocks between two executions
+    pub period: u64,
+}
+
+/// Operations that have to be proposed and wait for `Config::timelock` when it's configured
+#[cw_serde]
+pub enum TimelockedOperation {
+    WithdrawTokens {
+        recepient: Option<String>,
+        amount: Option<Uint128>,
+        denom: Option<String>,
+    },
+    WithdrawBalances {
+        recepient: Option<String>,
+        coins: Option<Vec<Coin>>,
+    },
+    SetTargetBalances {
+        target_balances: Vec<TargetBalance>,
+    },
+    AddTargetBalance {
+        target_balance: TargetBalance,
+    },
+    UpdateTargetBalance {
+        target_balance: TargetBalance,
+    },
+    RemoveTargetBalance {
+        address: String,
+        denom: String,
+    },
+    UpdateConfig {
+        new_config: ConfigOptional,
+    },
+    SetRefillConfig {
+        refill_config: RefillConfig,
+    },
+    SetFundingSource {
+        funding_source: FundingSource,
+    },
+    /// Only timelocked when it grants treasurer role with withdraw limits, other grants are
+    /// applied right away
+    GrantRole {
+        role: Role,
+        address: String,
+        #[serde(default)]
+        withdraw_limits: Vec<WithdrawLimit>,
+    },
+}
+
+impl TimelockedOperation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimelockedOperation::WithdrawTokens { .. } => "withdraw_tokens",
+            TimelockedOperation::WithdrawBalances { .. } => "withdraw_balances",
+            TimelockedOperation::SetTargetBalances { .. } => "set_target_balances",
+            TimelockedOperation::AddTargetBalance { .. } => "add_target_balance",
+            TimelockedOperation::UpdateTargetBalance { .. } => "update_target_balance",
+            TimelockedOperation::RemoveTargetBalance { .. } => "remove_target_balance",
+            TimelockedOperation::UpdateConfig { .. } => "update_config",
+            TimelockedOperation::SetRefillConfig { .. } => "set_refill_config",
+            TimelockedOperation::SetFundingSource { .. } => "set_funding_source",
+            TimelockedOperation::GrantRole { .. } => "grant_role",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct PendingOperation {
+    pub id: u64,
+    pub operation: TimelockedOperation,
+    /// Operation is executed on behalf of the proposer, so it must still be authorized then
+    pub proposer: Addr,
+    /// Operation can be executed by anyone once this expires and the current timelock has passed
+    /// since the operation was proposed
+    pub executable_at: Expiration,
+    pub proposed_height: u64,
+    pub proposed_time: Timestamp,
+}
+
+/// Set while the contract is paused
+#[cw_serde]
+pub struct PauseInfo {
+    pub paused_by: Addr,
+    pub height: u64,
+    pub time: Timestamp,
+    /// `WithdrawTokens` and `SponsorWithdraw` are blocked as well as distribution
+    pub withdrawals_paused: bool,
+}
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub owner: Option<Addr>,
     pub initial_target_balances: Vec<TargetBalance>,
+    pub config: Option<ConfigOptional>,
 }
 
 #[cw_serde]
 pub struct MigrateMsg {}
 
-#[cw_ownable::cw_ownable_query]
+#[cw_serde]
+pub enum SudoMsg {
+    /// IBC transfer to a remote target was acknowledged
+    Response {
+        request: RequestPacket,
+        data: Binary,
+    },
+    /// IBC transfer to a remote target failed, tokens are refunded to the contract
+    Error {
+        request: RequestPacket,
+        details: String,
+    },
+    /// IBC transfer to a remote target timed out, tokens are refunded to the contract
+    Timeout { request: RequestPacket },
+}
+
+#[cw_serde]
+pub struct PlannedTransfer {
+    pub address: String,
+    pub amount: Coin,
+    /// Full amount target lacks, greater than sent amount when target is only partially funded
+    pub deficit: Uint128,
+}
+
+#[cw_serde]
+pub enum SkipReason {
+    /// Target balance is not below threshold
+    Healthy,
+    /// Contract balance in target denom can't cover it
+    InsufficientFunds,
+    /// Target cooldown since the last top-up hasn't passed yet
+    Cooldown,
+    /// Target already received its spending cap within the current period
+    SpendingCapReached,
+    /// Balance of the remote or cw20 target can't be read
+    BalanceUnavailable,
+    /// Balance of the remote target is older than allowed or wasn't updated since its last top-up
+    StaleBalance,
+}
+
+#[cw_serde]
+pub struct SkippedTarget {
+    pub address: String,
+    pub denom: String,
+    pub reason: SkipReason,
+}
+
+#[cw_serde]
+pub struct SimulateDistributeResponse {
+    pub transfers: Vec<PlannedTransfer>,
+    pub skipped: Vec<SkippedTarget>,
+    /// Contract balances left after distribution in every denom that has registered targets
+    pub remaining_balances: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SpendingAllowance {
+    pub address: String,
+    pub denom: String,
+    /// Amount sent to the target within the current period, only tracked for capped targets
+    pub spent: Uint128,
+    /// Amount the target can still receive within the current period, `None` if not capped
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct BalanceQuery {
+    pub address: String,
+    pub denom: String,
+    /// Id of the interchain query reading balance of the remote target
+    pub query_id: u64,
+}
+
+/// Live state of a target as seen by the next distribution
+#[cw_serde]
+pub struct TargetStatus {
+    pub target: TargetBalance,
+    /// Current balance of the target, zero if balance of the remote target is unavailable or stale
+    pub balance: Uint128,
+    pub below_threshold: bool,
+    /// Amount target lacks up to its target balance, zero unless it's below threshold
+    pub deficit: Uint128,
+    /// Upper bound of what the next distribution can send to the target: other targets paid
+    /// from the same balance and IBC fees aren't accounted for
+    pub max_amount: Uint128,
+    /// Reason target won't be funded even if nothing else competes for its balance, `None`
+    /// doesn't guarantee it will be funded
+    pub skip_reason: Option<SkipReason>,
+    pub last_top_up: Option<TopUp>,
+}
+
+#[cw_serde]
+pub struct StaleTarget {
+    pub address: String,
+    pub denom: String,
+    /// Local height the last balance query result was submitted at
+    pub last_submitted_height: u64,
+}
+
+#[cw_serde]
+pub struct Deposit {
+    pub depositor: String,
+    pub denom: String,
+    /// Total amount deposited by the depositor in the denom
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct SponsorAccount {
+    pub sponsor: String,
+    pub denom: String,
+    /// Amount left in the sub-account
+    pub balance: Uint128,
+    /// Total amount spent on the sponsor's targets
+    pub spent: Uint128,
+}
+
+#[cw_serde]
+pub struct WithdrawAllowance {
+    pub denom: String,
+    pub cap: SpendingCap,
+    /// Amount withdrawn within the current period
+    pub withdrawn: Uint128,
+    /// Amount that can still be withdrawn within the current period
+    pub remaining: Uint128,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     #[returns(Vec<TargetBalance>)]
-    TargetBalances {},
-    #[returns(cosmwasm_std::Uint128)]
-    TargetBalance { address: Addr },
+    TargetBalances {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(TargetBalance)]
+    TargetBalance { address: Addr, denom: String },
+    #[returns(Vec<TargetStatus>)]
+    TargetStatuses {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(TargetStatus)]
+    TargetStatus { address: Addr, denom: String },
+    #[returns(Config)]
+    Config {},
+    #[returns(SimulateDistributeResponse)]
+    SimulateDistribute {},
+    #[returns(Option<TopUp>)]
+    LastTopUp { address: Addr, denom: String },
+    #[returns(Option<CronSchedule>)]
+    CronSchedule {},
+    #[returns(Option<RefillConfig>)]
+    RefillConfig {},
+    #[returns(Option<FundingSource>)]
+    FundingSource {},
+    #[returns(Vec<BalanceQuery>)]
+    BalanceQueries {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<StaleTarget>)]
+    StaleTargets {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<Deposit>)]
+    Deposits {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<SponsorAccount>)]
+    SponsorAccounts {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<SpendingAllowance>)]
+    SpendingAllowances {
+        start_after: Option<(Addr, String)>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<String>)]
+    RoleHolders {
+        role: Role,
+        start_after: Option<Addr>,
+        limit: Option<u32>,
+    },
+    #[returns(Vec<WithdrawAllowance>)]
+    WithdrawAllowances { address: Addr },
+    #[returns(Option<PauseInfo>)]
+    PauseStatus {},
+    #[returns(Vec<PendingOperation>)]
+    PendingOperations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Current owner of the contract, `None` if ownership was renounced
+    #[returns(Option<Addr>)]
+    Ownership {},
 }
 
 #[cw_ownable_execute]
 #[cw_serde]
 pub enum ExecuteMsg {
     Distribute {},
+    Deposit {},
+    /// Deposits sent funds into the sender's sponsor sub-account
+    SponsorDeposit {},
+    /// Withdraws from the sender's sponsor sub-account, the whole balance if `amount` is omitted
+    SponsorWithdraw {
+        denom: String,
+        amount: Option<Uint128>,
+    },
     WithdrawTokens {
         recepient: Option<String>,
         amount: Option<Uint128>,
+        denom: Option<String>,
+    },
+    /// Withdraws listed coins, every native balance of the contract if `coins` are omitted
+    WithdrawBalances {
+        recepient: Option<String>,
+        coins: Option<Vec<Coin>>,
     },
     SetTargetBalances {
         target_balances: Vec<TargetBalance>,
     },
+    AddTargetBalance {
+        target_balance: TargetBalance,
+    },
+    UpdateTargetBalance {
+        target_balance: TargetBalance,
+    },
+    RemoveTargetBalance {
+        address: String,
+        denom: String,
+    },
+    UpdateConfig {
+        new_config: ConfigOptional,
+    },
+    AddCronSchedule {
+        name: String,
+        period: u64,
+    },
+    RemoveCronSchedule {},
+    SetRefillConfig {
+        refill_config: RefillConfig,
+    },
+    RemoveRefillConfig {},
+    SetFundingSource {
+        funding_source: FundingSource,
+    },
+    RemoveFundingSource {},
+    /// Grants the role to the address, `withdraw_limits` are only allowed for treasurer and
+    /// replace the ones it had
+    GrantRole {
+        role: Role,
+        address: String,
+        #[serde(default)]
+        withdraw_limits: Vec<WithdrawLimit>,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    /// Blocks distribution and, if `withdrawals` is set, withdrawals until `Unpause`
+    Pause {
+        #[serde(default)]
+        withdrawals: bool,
+    },
+    Unpause {},
+    /// Queues the operation, it can be executed once `Config::timelock` has passed
+    ProposeOperation {
+        operation: TimelockedOperation,
+    },
+    CancelOperation {
+        id: u64,
+    },
+    ExecuteOperation {
+        id: u64,
+    },
 }
@@ -1,5 +1,58 @@
-use crate::msg::gas_distributor::TargetBalance;
-use cw_storage_plus::Item;
+use crate::msg::gas_distributor::{
+    Config, CronSchedule, FundingSource, PauseInfo, PendingOperation, RefillConfig, RemoteTarget,
+    SpendingCap, TargetBalanceUpdateParams, TopUp,
+};
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw_storage_plus::{Item, Map};
 
 pub static UNTRN_DENOM: &str = "untrn";
-pub const TARGET_BALANCES: Item<Vec<TargetBalance>> = Item::new("target_balances");
+/// Prefix of cw20 token keys, see `Token::key`
+pub static CW20_KEY_PREFIX: &str = "cw20:";
+/// Target balances keyed by (address, denom)
+pub const TARGET_BALANCES: Map<(&Addr, &str), TargetBalanceUpdateParams> =
+    Map::new("target_balances_map");
+/// Remote chain details of remote target balances keyed by (address, denom)
+pub const REMOTE_TARGETS: Map<(&Addr, &str), RemoteTarget> = Map::new("remote_targets");
+/// Balance interchain query ids of remote targets keyed by (address, denom)
+pub const REMOTE_BALANCE_QUERIES: Map<(&Addr, &str), u64> = Map::new("remote_balance_queries");
+/// Remote targets waiting for their balance query registration reply, in submission order
+pub const PENDING_BALANCE_QUERIES: Item<Vec<(Addr, String)>> = Item::new("pending_balance_queries");
+pub const CONFIG: Item<Config> = Item::new("config");
+/// Last top-up of every target keyed by (address, denom)
+pub const LAST_TOP_UPS: Map<(&Addr, &str), TopUp> = Map::new("last_top_ups");
+/// Top-ups within the current spending cap period of every capped target keyed by (address, denom)
+pub const TOP_UP_HISTORY: Map<(&Addr, &str), Vec<TopUp>> = Map::new("top_up_history");
+/// Total deposits of every depositor keyed by (depositor, denom)
+pub const DEPOSITS: Map<(&Addr, &str), Uint128> = Map::new("deposits");
+/// Sub-account balances of sponsors keyed by (sponsor, denom)
+pub const SPONSOR_BALANCES: Map<(&Addr, &str), Uint128> = Map::new("sponsor_balances");
+/// Total balance of sponsor sub-accounts keyed by denom
+pub const SPONSORED_TOTALS: Map<&str, Uint128> = Map::new("sponsored_totals");
+/// Total amount spent on targets of every sponsor keyed by (sponsor, denom)
+pub const SPONSOR_SPENT: Map<(&Addr, &str), Uint128> = Map::new("sponsor_spent");
+/// Cron schedule running `Distribute`, if registered
+pub const CRON_SCHEDULE: Item<CronSchedule> = Item::new("cron_schedule");
+/// Swap refilling contract `untrn` balance, refill is disabled when absent
+pub const REFILL_CONFIG: Item<RefillConfig> = Item::new("refill_config");
+/// Last refill swap, `amount` is the offered amount
+pub const LAST_REFILL: Item<TopUp> = Item::new("last_refill");
+/// Source funds are pulled from during distribution, nothing is pulled when absent
+pub const FUNDING_SOURCE: Item<FundingSource> = Item::new("funding_source");
+/// Pulls within the current pull cap period, amounts are the ones actually received
+pub const PULL_HISTORY: Item<Vec<TopUp>> = Item::new("pull_history");
+/// Token key and contract balance in it before the pull waiting for its reply, see `Token::key`
+pub const PENDING_PULL: Item<(String, Uint128)> = Item::new("pending_pull");
+/// Who paused the contract and when, contract isn't paused when absent
+pub const PAUSE: Item<PauseInfo> = Item::new("pause");
+/// Proposed timelocked operations keyed by id
+pub const PENDING_OPERATIONS: Map<u64, PendingOperation> = Map::new("pending_operations");
+/// Id of the next proposed operation
+pub const NEXT_OPERATION_ID: Item<u64> = Item::new("next_operation_id");
+/// Withdrawals within the current period of `Config::withdraw_limits` keyed by denom
+pub const WITHDRAW_HISTORY: Map<&str, Vec<TopUp>> = Map::new("withdraw_history");
+/// Holders of every granted role keyed by (role key, address), see `Role::key`
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+/// Withdraw limits of treasurers keyed by (treasurer, denom)
+pub const TREASURER_LIMITS: Map<(&Addr, &str), SpendingCap> = Map::new("treasurer_limits");
+/// Treasurer withdrawals within the current period of their limit keyed by (treasurer, denom)
+pub const TREASURER_WITHDRAWALS: Map<(&Addr, &str), Vec<TopUp>> = Map::new("treasurer_withdrawals");
@@ -5,6 +5,13 @@ pub fn response<A: Into<Attribute>, T>(
     contract_name: &str,
     attrs: impl IntoIterator<Item = A>,
 ) -> Response<T> {
-    Response::<T>::new()
-        .add_event(Event::new(format!("{}-{}", contract_name, ty)).add_attributes(attrs))
+    Response::<T>::new().add_event(event(ty, contract_name, attrs))
+}
+
+pub fn event<A: Into<Attribute>>(
+    ty: &str,
+    contract_name: &str,
+    attrs: impl IntoIterator<Item = A>,
+) -> Event {
+    Event::new(format!("{}-{}", contract_name, ty)).add_attributes(attrs)
 }
@@ -124,7 +124,7 @@ type CustomFn = dyn Fn(&QueryRequest<NeutronQuery>) -> Binary;
 
 pub struct WasmMockQuerier {
     base: MockQuerier<NeutronQuery>,
-    bank_query_responses: HashMap<String, Binary>,
+    bank_query_responses: HashMap<(String, String), Binary>,
     query_responses: HashMap<u64, Binary>,
     registered_queries: HashMap<u64, Binary>,
     wasm_query_responses: RefCell<HashMap<String, Vec<Box<WasmFn>>>>, // fml
@@ -150,8 +150,10 @@ impl Querier for WasmMockQuerier {
 impl WasmMockQuerier {
     pub fn handle_query(&self, request: &QueryRequest<NeutronQuery>) -> QuerierResult {
         match &request {
-            QueryRequest::Bank(BankQuery::Balance { address, .. }) => {
-                let custom_balance = self.bank_query_responses.get(address);
+            QueryRequest::Bank(BankQuery::Balance { address, denom }) => {
+                let custom_balance = self
+                    .bank_query_responses
+                    .get(&(address.to_string(), denom.to_string()));
 
                 if let Some(balance) = custom_balance {
                     SystemResult::Ok(ContractResult::Ok(balance.clone()))
@@ -270,8 +272,10 @@ impl WasmMockQuerier {
     }
 
     pub fn add_bank_query_response(&mut self, address: String, response: BalanceResponse) {
-        self.bank_query_responses
-            .insert(address, to_json_binary(&response).unwrap());
+        self.bank_query_responses.insert(
+            (address, response.amount.denom.clone()),
+            to_json_binary(&response).unwrap(),
+        );
     }
     pub fn add_query_response(&mut self, query_id: u64, response: Binary) {
         self.query_responses.insert(query_id, response);